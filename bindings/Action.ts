@@ -25,6 +25,14 @@ export type Action =
     step_true: string;
     step_false: string;
   }
+  | { action: "switch_mode"; mode: string }
+  | {
+      action: "call_shortcut";
+      id: string;
+      input: string;
+      args: Record<string, string>;
+      export: Array<string>;
+    }
   | { action: "spawn"; command: string; args: Array<string> }
   | { action: "print_console"; content: string }
   | { action: "show_dialog"; title: string; body: string }
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type MouseButtonDef =
+  | "LeftButton"
+  | "MiddleButton"
+  | "RightButton"
+  | "X1Button"
+  | "X2Button";
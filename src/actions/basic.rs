@@ -1,21 +1,27 @@
 use std::collections::HashMap;
 
+use anyhow::Context;
 use native_dialog::{MessageDialog, MessageType};
 
 use crate::evaluation::replace_variables_tag;
-use crate::hotkey::ShortcutResult;
+use crate::hotkey::{ShortcutResult, TriggerContext};
 
 pub struct BasicAction;
 
 impl BasicAction {
     pub fn sleep(
+        context: &TriggerContext,
         input_str: &str,
         variables: &HashMap<String, String>,
         duration_ms: &str,
     ) -> anyhow::Result<ShortcutResult> {
         let duration_ms = replace_variables_tag(duration_ms, input_str, variables);
-        let duration_ms = duration_ms.parse::<u64>().unwrap();
-        std::thread::sleep(std::time::Duration::from_millis(duration_ms));
+        let duration_ms = duration_ms
+            .parse::<u64>()
+            .context("duration_ms must be a valid positive integer")?;
+        context
+            .cancel
+            .sleep(std::time::Duration::from_millis(duration_ms))?;
         Ok(ShortcutResult::Success(input_str.to_string()))
     }
 
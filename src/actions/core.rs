@@ -1,12 +1,19 @@
 use std::collections::HashMap;
-use std::process::Command;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
 use anyhow::{anyhow, Context};
 
 use crate::evaluation::{replace_variables_tag, replace_variables_tag_vec, Comparison};
-use crate::hotkey::ShortcutResult;
+use crate::hotkey::{ShortcutResult, TriggerContext};
+use crate::mode::{set_current_mode, MODE_VARIABLE};
 use crate::Config;
 
+/// Interval to check if a spawned command exited or its shortcut run was cancelled.
+const SPAWN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
 pub struct CoreAction;
 
 impl CoreAction {
@@ -147,7 +154,64 @@ impl CoreAction {
         })
     }
 
+    pub fn switch_mode(
+        config: &Config,
+        input_str: &str,
+        variables: &mut HashMap<String, String>,
+        mode: &str,
+    ) -> anyhow::Result<ShortcutResult> {
+        let mode = replace_variables_tag(mode, input_str, variables);
+        if !config.has_mode(&mode) {
+            return Err(anyhow!("Mode `{mode}` does not exist"));
+        }
+        set_current_mode(&mode);
+        variables.insert(MODE_VARIABLE.to_string(), mode.clone());
+        println!("Switched to mode `{mode}`");
+        Ok(ShortcutResult::Success(input_str.to_string()))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn call_shortcut(
+        config: &Config,
+        context: &TriggerContext,
+        input_str: &str,
+        variables: &mut HashMap<String, String>,
+        id: &str,
+        input: &str,
+        args: &HashMap<String, String>,
+        export: &Vec<String>,
+    ) -> anyhow::Result<ShortcutResult> {
+        let id = replace_variables_tag(id, input_str, variables);
+        let shortcut = config
+            .find_shortcut_by_id(&id)
+            .ok_or_else(|| anyhow!("No shortcut found with id `{id}`"))?;
+
+        let input = replace_variables_tag(input, input_str, variables);
+        let args = args
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.clone(),
+                    replace_variables_tag(value, input_str, variables),
+                )
+            })
+            .collect();
+
+        let result = shortcut
+            .trigger_with(config, &context.nested()?, &input, args)
+            .with_context(|| format!("Call to shortcut `{id}` failed"))?;
+
+        for name in export {
+            let name = name.to_lowercase();
+            if let Some(value) = result.variables.get(&name) {
+                variables.insert(name, value.clone());
+            }
+        }
+        Ok(ShortcutResult::Success(result.output))
+    }
+
     pub fn spawn(
+        context: &TriggerContext,
         input_str: &str,
         variables: &HashMap<String, String>,
         command: &str,
@@ -156,19 +220,38 @@ impl CoreAction {
         let command = replace_variables_tag(command, input_str, variables);
         let args = replace_variables_tag_vec(args, input_str, variables);
 
-        let mut command = Command::new(command);
-        args.iter().for_each(|arg| {
-            command.arg(arg);
-        });
-        let command = command.output().expect("Failed to execute command");
-        if command.status.success() {
+        let mut child = Command::new(&command)
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to execute command `{command}`"))?;
+
+        // Read the output meanwhile, else the command blocks once the pipes are full
+        let stdout = read_in_background(child.stdout.take());
+        let stderr = read_in_background(child.stderr.take());
+
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if let Err(e) = context.cancel.sleep(SPAWN_POLL_INTERVAL) {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(e);
+            }
+        };
+
+        let stdout = stdout.join().unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+        if status.success() {
             Ok(ShortcutResult::Success(
-                String::from_utf8_lossy(&command.stdout).to_string(),
+                String::from_utf8_lossy(&stdout).to_string(),
             ))
         } else {
             Err(anyhow!(
                 "Command failed: {}",
-                String::from_utf8_lossy(&command.stderr)
+                String::from_utf8_lossy(&stderr)
             ))
         }
     }
@@ -181,14 +264,14 @@ impl CoreAction {
     ) -> anyhow::Result<ShortcutResult> {
         let amount: i64 = replace_variables_tag(amount.to_string().as_str(), input_str, variables)
             .parse::<i64>()
-            .unwrap();
+            .context("amount must be a valid integer")?;
 
         let new_value = if variables.contains_key(name.to_lowercase().as_str()) {
             let value = variables
                 .get(name.to_lowercase().as_str())
                 .unwrap()
                 .parse::<i64>()
-                .unwrap();
+                .with_context(|| format!("variable `{name}` must be a valid integer"))?;
             (value + amount).to_string()
         } else {
             amount.to_string()
@@ -199,13 +282,13 @@ impl CoreAction {
     }
 }
 
-fn parse_step(step: &str) -> anyhow::Result<usize> {
+pub(crate) fn parse_step(step: &str) -> anyhow::Result<usize> {
     Ok(step
         .parse::<usize>()
         .context("step must be a valid integer")?)
 }
 
-fn parse_step_relative(step: &str) -> anyhow::Result<(usize, bool)> {
+pub(crate) fn parse_step_relative(step: &str) -> anyhow::Result<(usize, bool)> {
     let sign_is_positive = !step.contains("-");
     let step = step
         .replace("-", "")
@@ -214,3 +297,14 @@ fn parse_step_relative(step: &str) -> anyhow::Result<(usize, bool)> {
         .context("step must be a valid integer")?;
     Ok((step, sign_is_positive))
 }
+
+/// Read a pipe of a spawned command until it is closed.
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut output);
+        }
+        output
+    })
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
@@ -9,13 +10,15 @@ use crate::actions::core::CoreAction;
 use crate::actions::openai::OpenAIAction;
 use crate::config::Config;
 use crate::evaluation::{SerializedComparison};
-use crate::hotkey::ShortcutResult;
+use crate::hotkey::{ShortcutResult, TriggerContext};
 
 mod basic;
 mod clipboard;
 mod core;
 mod openai;
 
+pub(crate) use self::core::{parse_step, parse_step_relative};
+
 /// Actions are synchronous functions that take some input and return some output, they can do
 /// anything.
 ///
@@ -31,7 +34,7 @@ mod openai;
 ///
 /// You may want to start
 /// your list of actions with an action that read some data as input for the next actions.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "action", rename_all = "snake_case")]
 #[derive(TS)]
 #[ts(export)]
@@ -58,7 +61,7 @@ pub enum Action {
         /// Name of the variable to clear.
         name: String,
     },
-    /// Wait for a given duration. Returns input.
+    /// Wait for a given duration, stops early if the shortcut run is cancelled. Returns input.
     Sleep {
         /// Duration of the sleep in milliseconds.
         duration_ms: String,
@@ -156,7 +159,35 @@ pub enum Action {
         /// Step index to go to in the list of actions (starts at 0) if condition is false.
         step_false: String,
     },
-    /// Spawn a system command. Returns the result of the command.
+    /// Switch to another mode, only the shortcuts of this mode will be active. Returns input.
+    ///
+    /// The current mode is available in the variable `mode`. The shortcuts of the top-level
+    /// `keyboard_shortcuts` are in the `normal` mode.
+    SwitchMode {
+        /// Name of the mode to switch to.
+        mode: String,
+    },
+    /// Run the actions of another shortcut, like a function. Returns the output of the last
+    /// action of the called shortcut.
+    ///
+    /// The called shortcut starts with its own variables, the variables of the caller are not
+    /// shared. Use `args` to pass variables and `export` to get some back.
+    CallShortcut {
+        /// Id of the shortcut to call.
+        id: String,
+        /// Input of the first action of the called shortcut. Default is `{{input}}`.
+        #[serde(default = "input_tag_str")]
+        input: String,
+        /// Variables to set in the called shortcut. Default is empty.
+        #[serde(default)]
+        args: HashMap<String, String>,
+        /// Names of the variables of the called shortcut to copy back once it ended. Default is
+        /// empty list.
+        #[serde(default)]
+        export: Vec<String>,
+    },
+    /// Spawn a system command, killed if the shortcut run is cancelled. Returns the result of the
+    /// command.
     Spawn {
         /// Command to execute.
         command: String,
@@ -206,6 +237,7 @@ impl Action {
     pub fn run(
         &self,
         config: &Config,
+        context: &TriggerContext,
         input_str: &str,
         variables: &mut HashMap<String, String>,
     ) -> anyhow::Result<ShortcutResult> {
@@ -220,7 +252,9 @@ impl Action {
             Action::DeleteVariable { name } => {
                 CoreAction::delete_variable(input_str, variables, name)
             }
-            Action::Sleep { duration_ms } => BasicAction::sleep(input_str, variables, duration_ms),
+            Action::Sleep { duration_ms } => {
+                BasicAction::sleep(context, input_str, variables, duration_ms)
+            }
             Action::EndProgram => CoreAction::end_program(input_str),
             Action::GoToStep { step } => CoreAction::go_to_step(input_str, variables, step),
             Action::GoToStepRelative { step } => {
@@ -242,8 +276,19 @@ impl Action {
             } => CoreAction::if_else_relative(
                 input_str, variables, operation, a, b, step_true, step_false,
             ),
+            Action::SwitchMode { mode } => {
+                CoreAction::switch_mode(config, input_str, variables, mode)
+            }
+            Action::CallShortcut {
+                id,
+                input,
+                args,
+                export,
+            } => CoreAction::call_shortcut(
+                config, context, input_str, variables, id, input, args, export,
+            ),
             Action::Spawn { command, args } => {
-                CoreAction::spawn(input_str, variables, command, args)
+                CoreAction::spawn(context, input_str, variables, command, args)
             }
 
             Action::PrintConsole { content } => {
@@ -21,7 +21,8 @@ impl OpenAIAction {
     ) -> anyhow::Result<ShortcutResult> {
         let pre_prompt = replace_variables_tag(pre_prompt, input_str, variables);
         let prompt = replace_variables_tag(prompt, input_str, variables);
-        let res = ask_chatgpt(&pre_prompt, &prompt, config.openai_api_key.as_str())?;
+        let api_key = config.openai_api_key.resolve()?;
+        let res = ask_chatgpt(&pre_prompt, &prompt, api_key.as_str())?;
         Ok(ShortcutResult::Success(res))
     }
 }
@@ -1,178 +1,701 @@
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::prelude::*;
-use std::path::PathBuf;
-use std::{env, fs};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{env, fs, iter, ptr};
 
 use anyhow::{anyhow, Context};
 use inputbot::KeybdKey;
+use schemars::schema::RootSchema;
+use schemars::{schema_for, JsonSchema};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_yaml::with::singleton_map_recursive;
 
 use crate::actions::Action;
 use crate::evaluation::{NumberOperator, SerializedComparison, StringOperator};
 use crate::hotkey::{KeyboardKey, Shortcut};
+use crate::keys::{deserialize_key_combo, key_combo_schema};
+use crate::migration::{get_config_version, migrate_config, needs_migration, CONFIG_VERSION};
+use crate::mode::DEFAULT_MODE;
+use crate::secret::Secret;
+use crate::validation::{has_errors, validate_config, Severity};
 
-const CONFIG_FILE_NAME: &'static str = "shortcut-hero.json";
+/// Configuration file names looked up, by order of preference
+const CONFIG_FILE_NAMES: [&str; 4] = [
+    "shortcut-hero.json",
+    "shortcut-hero.toml",
+    "shortcut-hero.yaml",
+    "shortcut-hero.yml",
+];
+const CONFIG_DIR_NAME: &str = "shortcut-hero";
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+const DEFAULT_SEQUENCE_TIMEOUT_MS: u64 = 1000;
+const DEFAULT_MAX_CONCURRENT_RUNS: usize = 4;
+
+/// Configuration of Shortcut Hero.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
 pub struct Config {
+    /// JSON Schema of the configuration file, for editors autocompletion and validation.
+    #[serde(rename = "$schema", default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+
+    /// Version of the configuration format. Older configuration files are upgraded automatically
+    /// when loaded, a backup of the original file is kept.
+    #[serde(default)]
+    pub version: u32,
+
+    /// Other configuration files to merge into this one, paths or glob patterns relative to this
+    /// file (e.g. `team/*.json`).
+    ///
+    /// Included files are merged in order, then this file is merged last. A shortcut with the same
+    /// `id` as a shortcut from a previously merged file replaces it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+
+    /// OpenAI API key, only required if you use the `ask_chatgpt` action.
+    ///
+    /// Either the key itself or where to read it from, e.g. `{"from_env": "OPENAI_API_KEY"}`.
+    #[serde(default)]
+    pub openai_api_key: Secret,
+
+    /// Variables available in every shortcut (e.g. `{{language}}`), a shortcut can override them
+    /// with its own `variables`. Variables names are case-insensitive.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, String>,
+
+    /// Maximum delay between two key combos of a shortcut `sequence`, in milliseconds. Default is
+    /// `1000`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(default = "default_sequence_timeout_ms")]
+    pub sequence_timeout_ms: Option<u64>,
+
+    /// Maximum number of shortcuts running at the same time, the other triggered shortcuts wait
+    /// for a run to finish. Default is `4`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(default = "default_max_concurrent_runs")]
+    pub max_concurrent_runs: Option<usize>,
+
+    /// Keys to cancel every shortcut run in flight, in any mode, e.g. `Ctrl+Alt+Escape`.
+    ///
+    /// The runs stop before their next action, a `sleep` is interrupted and a `spawn` command is
+    /// killed.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_key_combo",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[schemars(schema_with = "key_combo_schema")]
+    pub abort_keys: Vec<KeyboardKey>,
+
+    /// List of keyboard shortcuts and the actions they trigger, active in the default `normal`
+    /// mode.
+    #[serde(default)]
+    pub keyboard_shortcuts: Vec<Shortcut>,
+
+    /// Other sets of shortcuts by mode name, e.g. `writing` or `gaming`. Only the shortcuts of the
+    /// current mode are active, switch mode with the `switch_mode` action.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub modes: BTreeMap<String, Mode>,
+
+    /// Files the configuration was loaded from, the configuration file and the included files.
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub loaded_files: Vec<PathBuf>,
+}
+
+/// Default of `sequence_timeout_ms` shown in the JSON Schema, the field stays unset so an included
+/// file does not override it.
+fn default_sequence_timeout_ms() -> Option<u64> {
+    Some(DEFAULT_SEQUENCE_TIMEOUT_MS)
+}
+
+/// Default of `max_concurrent_runs` shown in the JSON Schema.
+fn default_max_concurrent_runs() -> Option<usize> {
+    Some(DEFAULT_MAX_CONCURRENT_RUNS)
+}
+
+/// A named set of shortcuts, replacing the shortcuts of the `normal` mode while active.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct Mode {
+    /// Description of the mode.
     #[serde(default)]
-    pub openai_api_key: String,
+    pub description: String,
 
+    /// List of keyboard shortcuts active in this mode. Leave empty to disable every shortcut, but
+    /// then there is no way back to another mode.
     #[serde(default)]
     pub keyboard_shortcuts: Vec<Shortcut>,
 }
 
 impl Config {
-    pub fn load_config() -> Config {
-        let config = load_config_from_file().unwrap();
-        println!("Config: {config:#?}\n\n");
-        assert_config_valid(&config);
-        config
+    /// Load and validate the configuration file, an example configuration file is created if it
+    /// does not exist.
+    ///
+    /// Validation warnings are printed, validation errors make the loading fail. Older
+    /// configuration files are upgraded in memory only, the files are left untouched.
+    pub fn load_config(config_path: &Path) -> anyhow::Result<Config> {
+        load_validated_config(config_path, false)
+    }
+
+    /// Load and validate the configuration file like `load_config`, older configuration files are
+    /// upgraded and saved, with a backup of the original files.
+    pub fn load_and_upgrade_config(config_path: &Path) -> anyhow::Result<Config> {
+        load_validated_config(config_path, true)
+    }
+
+    /// Load the configuration file and its included files without validating it.
+    pub fn read_config(config_path: &Path) -> anyhow::Result<Config> {
+        load_config_with_includes(config_path, &mut Vec::new(), false)
+    }
+
+    /// Load the configuration file as is, without merging its included files nor validating it.
+    pub fn read_config_without_includes(config_path: &Path) -> anyhow::Result<Config> {
+        load_config_from_file(config_path, false)
+    }
+
+    pub fn save_config(config: &Config, config_path: &Path) -> anyhow::Result<()> {
+        save_config_to_file(config, config_path)
+    }
+
+    /// Create a configuration file with some example shortcuts.
+    pub fn init_config(config_path: &Path) -> anyhow::Result<()> {
+        init_config_file(config_path)
+    }
+
+    /// Get the JSON Schema of the configuration file.
+    pub fn json_schema() -> RootSchema {
+        schema_for!(Config)
     }
 
-    pub fn save_config(config: &Config) {
-        save_config_to_file(config).unwrap();
+    /// Find the shortcut matching `selector`, either its id, its index in `keyboard_shortcuts` or
+    /// its exact description.
+    pub fn find_shortcut(&self, selector: &str) -> Option<&Shortcut> {
+        if let Some(shortcut) = self.find_shortcut_by_id(selector) {
+            return Some(shortcut);
+        }
+        match selector.parse::<usize>() {
+            Ok(index) => self.keyboard_shortcuts.get(index),
+            Err(_) => self
+                .keyboard_shortcuts
+                .iter()
+                .find(|shortcut| shortcut.description == selector),
+        }
+    }
+
+    /// Find a shortcut by its id, in every mode.
+    pub fn find_shortcut_by_id(&self, id: &str) -> Option<&Shortcut> {
+        self.all_shortcuts()
+            .find(|shortcut| shortcut.id.as_deref() == Some(id))
+    }
+
+    /// Get the shortcuts of every mode.
+    pub fn all_shortcuts(&self) -> impl Iterator<Item = &Shortcut> {
+        self.shortcuts_by_mode()
+            .flat_map(|(_, shortcuts)| shortcuts.iter())
+    }
+
+    /// Get the shortcuts of every mode, to modify them.
+    pub fn shortcuts_mut(&mut self) -> impl Iterator<Item = &mut Shortcut> {
+        self.keyboard_shortcuts.iter_mut().chain(
+            self.modes
+                .values_mut()
+                .flat_map(|mode| mode.keyboard_shortcuts.iter_mut()),
+        )
+    }
+
+    /// Get the name and the shortcuts of every mode, starting with the default `normal` mode.
+    pub fn shortcuts_by_mode(&self) -> impl Iterator<Item = (&str, &[Shortcut])> {
+        iter::once((DEFAULT_MODE, self.keyboard_shortcuts.as_slice())).chain(
+            self.modes
+                .iter()
+                .map(|(name, mode)| (name.as_str(), mode.keyboard_shortcuts.as_slice())),
+        )
+    }
+
+    /// Get the mode and the index of a shortcut of this configuration, `None` if `shortcut` does
+    /// not belong to it. The shortcut is found by reference, not by value, two shortcuts with the
+    /// same settings are still two shortcuts.
+    pub fn shortcut_position(&self, shortcut: &Shortcut) -> Option<(&str, usize)> {
+        self.shortcuts_by_mode().find_map(|(mode, shortcuts)| {
+            shortcuts
+                .iter()
+                .position(|other| ptr::eq(other, shortcut))
+                .map(|index| (mode, index))
+        })
+    }
+
+    /// Get the shortcuts active in a mode, empty if the mode does not exist.
+    pub fn shortcuts_for_mode(&self, mode: &str) -> &[Shortcut] {
+        if mode == DEFAULT_MODE {
+            return &self.keyboard_shortcuts;
+        }
+        match self.modes.get(mode) {
+            Some(mode) => &mode.keyboard_shortcuts,
+            None => &[],
+        }
+    }
+
+    /// Whether a mode exists, the default `normal` mode always exists.
+    pub fn has_mode(&self, mode: &str) -> bool {
+        mode == DEFAULT_MODE || self.modes.contains_key(mode)
+    }
+
+    /// Maximum delay between two key combos of a shortcut `sequence`.
+    pub fn sequence_timeout(&self) -> Duration {
+        Duration::from_millis(
+            self.sequence_timeout_ms
+                .unwrap_or(DEFAULT_SEQUENCE_TIMEOUT_MS),
+        )
+    }
+
+    /// Maximum number of shortcuts running at the same time.
+    pub fn max_concurrent_runs(&self) -> usize {
+        self.max_concurrent_runs
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_RUNS)
+    }
+
+    /// Make the relative paths of a loaded configuration file relative to its directory.
+    fn resolve_relative_paths(&mut self, base_dir: &Path) {
+        self.openai_api_key.resolve_relative_path(base_dir);
+        for shortcut in self.shortcuts_mut() {
+            if let Some(watch) = &mut shortcut.watch {
+                watch.resolve_relative_path(base_dir);
+            }
+        }
+    }
+
+    /// Merge `other` into this configuration, values from `other` take precedence.
+    fn merge(&mut self, other: Config) {
+        if !other.openai_api_key.is_empty() {
+            self.openai_api_key = other.openai_api_key;
+        }
+
+        self.variables.extend(other.variables);
+        if other.sequence_timeout_ms.is_some() {
+            self.sequence_timeout_ms = other.sequence_timeout_ms;
+        }
+        if other.max_concurrent_runs.is_some() {
+            self.max_concurrent_runs = other.max_concurrent_runs;
+        }
+        if !other.abort_keys.is_empty() {
+            self.abort_keys = other.abort_keys;
+        }
+        merge_shortcuts(&mut self.keyboard_shortcuts, other.keyboard_shortcuts);
+        for (name, other_mode) in other.modes {
+            let mode = self.modes.entry(name).or_default();
+            if !other_mode.description.is_empty() {
+                mode.description = other_mode.description;
+            }
+            merge_shortcuts(&mut mode.keyboard_shortcuts, other_mode.keyboard_shortcuts);
+        }
+
+        self.loaded_files.extend(other.loaded_files);
+    }
+}
+
+/// Add `other` shortcuts, a shortcut with the same id as a shortcut of a previously merged file
+/// replaces it
+///
+/// Shortcuts with the same id in `other` are all kept, for the validation to report them.
+fn merge_shortcuts(shortcuts: &mut Vec<Shortcut>, other: Vec<Shortcut>) {
+    let merged_count = shortcuts.len();
+    let mut replaced = Vec::new();
+    for shortcut in other {
+        let overridden = shortcut.id.as_ref().and_then(|id| {
+            shortcuts[..merged_count]
+                .iter()
+                .position(|existing| existing.id.as_ref() == Some(id))
+        });
+        match overridden {
+            Some(i) if !replaced.contains(&i) => {
+                shortcuts[i] = shortcut;
+                replaced.push(i);
+            }
+            _ => shortcuts.push(shortcut),
+        }
     }
 }
 
-fn load_config_from_file() -> anyhow::Result<Config> {
-    let config_path = get_config_path();
+/// Get the path of the configuration file to use.
+///
+/// The first match wins:
+///
+/// 1. `explicit_path`, when provided (`--config <path>`)
+/// 2. `shortcut-hero.{json,toml,yaml,yml}` in the current working directory, if it exists
+/// 3. `$XDG_CONFIG_HOME/shortcut-hero/shortcut-hero.{json,toml,yaml,yml}` (or the OS
+///    configuration directory), defaults to `shortcut-hero.json` if none exists
+pub fn resolve_config_path(explicit_path: Option<&Path>) -> PathBuf {
+    if let Some(path) = explicit_path {
+        return path.to_path_buf();
+    }
+
+    if let Ok(current_dir) = env::current_dir() {
+        if let Some(path) = find_config_file(&current_dir) {
+            return path;
+        }
+    }
+
+    match get_config_dir() {
+        Some(config_dir) => {
+            let config_dir = config_dir.join(CONFIG_DIR_NAME);
+            find_config_file(&config_dir).unwrap_or_else(|| config_dir.join(CONFIG_FILE_NAMES[0]))
+        }
+        None => PathBuf::from(CONFIG_FILE_NAMES[0]),
+    }
+}
+
+fn find_config_file(dir: &Path) -> Option<PathBuf> {
+    CONFIG_FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists())
+}
+
+fn get_config_dir() -> Option<PathBuf> {
+    match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => dirs::config_dir(),
+    }
+}
+
+/// Format of a configuration file, picked from the file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    pub fn from_path(config_path: &Path) -> anyhow::Result<ConfigFormat> {
+        let extension = config_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        match extension.as_str() {
+            "json" => Ok(ConfigFormat::Json),
+            "toml" => Ok(ConfigFormat::Toml),
+            "yaml" | "yml" => Ok(ConfigFormat::Yaml),
+            _ => Err(anyhow!(
+                "Unsupported config file extension for {config_path:?}, \
+                expected `.json`, `.toml`, `.yaml` or `.yml`"
+            )),
+        }
+    }
+
+    /// Parse a configuration file without mapping it to `Config`, used to upgrade older
+    /// configuration files.
+    pub fn parse_raw(&self, data: &str) -> anyhow::Result<serde_json::Value> {
+        let config = match self {
+            ConfigFormat::Json => serde_json::from_str(data)?,
+            ConfigFormat::Toml => toml::from_str(data)?,
+            ConfigFormat::Yaml => parse_yaml(data)?,
+        };
+        Ok(config)
+    }
+
+    pub fn parse(&self, data: &str) -> anyhow::Result<Config> {
+        let config = match self {
+            ConfigFormat::Json => serde_json::from_str(data)?,
+            ConfigFormat::Toml => toml::from_str(data)?,
+            ConfigFormat::Yaml => parse_yaml(data)?,
+        };
+        Ok(config)
+    }
+
+    pub fn serialize(&self, config: &Config) -> anyhow::Result<String> {
+        let data = match self {
+            ConfigFormat::Json => serde_json::to_string_pretty(config)?,
+            ConfigFormat::Toml => toml::to_string_pretty(config)?,
+            ConfigFormat::Yaml => {
+                let mut data = Vec::new();
+                singleton_map_recursive::serialize(
+                    config,
+                    &mut serde_yaml::Serializer::new(&mut data),
+                )?;
+                String::from_utf8(data)?
+            }
+        };
+        Ok(data)
+    }
+}
+
+/// Parse YAML with the enums written as maps, e.g. `schedule: { every_ms: 60000 }` like in JSON
+/// and TOML, instead of the YAML tags `schedule: !every_ms 60000`.
+fn parse_yaml<T: DeserializeOwned>(data: &str) -> anyhow::Result<T> {
+    Ok(singleton_map_recursive::deserialize(
+        serde_yaml::Deserializer::from_str(data),
+    )?)
+}
+
+fn load_validated_config(config_path: &Path, save_upgrade: bool) -> anyhow::Result<Config> {
     if !config_path.exists() {
-        init_config_file();
+        init_config_file(config_path)?;
+        return Err(anyhow!(
+            "Config file not found, created an example config file at {config_path:?}",
+        ));
+    }
+    let config = load_config_with_includes(config_path, &mut Vec::new(), save_upgrade)?;
+
+    let diagnostics = validate_config(&config);
+    diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Warning)
+        .for_each(|diagnostic| eprintln!("{diagnostic}"));
+    if has_errors(&diagnostics) {
+        let errors: Vec<String> = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .map(|diagnostic| diagnostic.to_string())
+            .collect();
+        return Err(anyhow!(
+            "Invalid config file {config_path:?}\n{}",
+            errors.join("\n")
+        ));
+    }
+    Ok(config)
+}
+
+/// Load a configuration file and recursively merge its included files, `parents` is the chain of
+/// files including this one, to detect include cycles.
+fn load_config_with_includes(
+    config_path: &Path,
+    parents: &mut Vec<PathBuf>,
+    save_upgrade: bool,
+) -> anyhow::Result<Config> {
+    let canonical_path = config_path
+        .canonicalize()
+        .with_context(|| format!("Config file {config_path:?} not found"))?;
+    if parents.contains(&canonical_path) {
         return Err(anyhow!(
-            "Config file not found, created an empty config file at {}",
-            CONFIG_FILE_NAME,
+            "Config file {config_path:?} is included in a cycle"
         ));
     }
-    let data = fs::read_to_string(&config_path)
+
+    let mut file_config = load_config_from_file(config_path, save_upgrade)?;
+    if let Some(base_dir) = canonical_path.parent() {
+        file_config.resolve_relative_paths(base_dir);
+    }
+    let include = std::mem::take(&mut file_config.include);
+    file_config.loaded_files = vec![canonical_path.clone()];
+
+    parents.push(canonical_path);
+    let mut config = Config::default();
+    for included_path in expand_include_patterns(config_path, &include)? {
+        let included_config = load_config_with_includes(&included_path, parents, save_upgrade)
+            .with_context(|| format!("Failed to include {included_path:?} from {config_path:?}"))?;
+        config.merge(included_config);
+    }
+    parents.pop();
+
+    config.merge(file_config);
+
+    // Only remove disabled shortcuts once everything is merged, a later file may enable them again
+    if parents.is_empty() {
+        config
+            .keyboard_shortcuts
+            .retain(|shortcut| !shortcut.disabled);
+        config.modes.values_mut().for_each(|mode| {
+            mode.keyboard_shortcuts
+                .retain(|shortcut| !shortcut.disabled)
+        });
+    }
+    Ok(config)
+}
+
+/// Resolve the `include` paths and glob patterns, relative to the including config file.
+fn expand_include_patterns(config_path: &Path, include: &[String]) -> anyhow::Result<Vec<PathBuf>> {
+    let base_dir = config_path.parent().unwrap_or(Path::new("."));
+    let mut paths = Vec::new();
+    for pattern in include {
+        let full_pattern = base_dir.join(pattern);
+        if !pattern.contains(['*', '?', '[']) {
+            paths.push(full_pattern);
+            continue;
+        }
+
+        let full_pattern = full_pattern
+            .to_str()
+            .ok_or_else(|| anyhow!("Invalid include pattern `{pattern}`"))?;
+        let matches = glob::glob(full_pattern)
+            .with_context(|| format!("Invalid include pattern `{pattern}`"))?;
+        for path in matches {
+            paths.push(path?);
+        }
+    }
+    Ok(paths)
+}
+
+/// Load a configuration file, an older configuration file is upgraded, and only saved with
+/// `save_upgrade`.
+fn load_config_from_file(config_path: &Path, save_upgrade: bool) -> anyhow::Result<Config> {
+    let format = ConfigFormat::from_path(config_path)?;
+    let data = fs::read_to_string(config_path)
         .with_context(|| format!("Failed to read config file at {config_path:?}"))?;
-    let config: Config = serde_json::from_str(data.as_str())?;
+    let raw_config = format
+        .parse_raw(data.as_str())
+        .with_context(|| format!("Failed to parse config file at {config_path:?}"))?;
+
+    if !needs_migration(&raw_config)? {
+        // Parse again from the original data to get error messages with line numbers
+        return format
+            .parse(data.as_str())
+            .with_context(|| format!("Failed to parse config file at {config_path:?}"));
+    }
+    upgrade_config_file(config_path, raw_config, save_upgrade)
+}
+
+/// Upgrade an older configuration file to the current version. With `save`, the upgraded
+/// configuration is saved and the original file is backed up next to it as
+/// `<file>.v<version>.bak`.
+fn upgrade_config_file(
+    config_path: &Path,
+    mut raw_config: serde_json::Value,
+    save: bool,
+) -> anyhow::Result<Config> {
+    let version = get_config_version(&raw_config)?;
+    let changes = migrate_config(&mut raw_config)
+        .with_context(|| format!("Failed to upgrade config file at {config_path:?}"))?;
+    let config: Config = serde_json::from_value(raw_config)
+        .with_context(|| format!("Failed to parse config file at {config_path:?}"))?;
+    if !save {
+        eprintln!(
+            "Config file {config_path:?} is for version {version}, upgraded in memory only, \
+             `shortcut-hero run` saves the upgrade"
+        );
+        return Ok(config);
+    }
+
+    let mut backup_path = config_path.as_os_str().to_owned();
+    backup_path.push(format!(".v{version}.bak"));
+    let backup_path = PathBuf::from(backup_path);
+    fs::copy(config_path, &backup_path)
+        .with_context(|| format!("Could not back up config file to {backup_path:?}"))?;
+    save_config_to_file(&config, config_path)?;
+
+    println!("Upgraded config file {config_path:?}, backup saved to {backup_path:?}");
+    changes.iter().for_each(|change| println!("  - {change}"));
     Ok(config)
 }
 
-fn save_config_to_file(config: &Config) -> anyhow::Result<()> {
-    let config_path = get_config_path();
+fn save_config_to_file(config: &Config, config_path: &Path) -> anyhow::Result<()> {
+    let format = ConfigFormat::from_path(config_path)?;
+    let data = format.serialize(config)?;
 
-    let mut file = File::create(&config_path)?;
-    let json = serde_json::to_string_pretty(config)?;
+    if let Some(parent) = config_path.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Could not create config directory {parent:?}"))?;
+        }
+    }
 
-    file.write_all(json.as_bytes())
+    let mut file = File::create(config_path)
+        .with_context(|| format!("Could not create config file {config_path:?}"))?;
+    file.write_all(data.as_bytes())
         .with_context(|| format!("Could not save config to {config_path:?}"))
 }
 
-fn assert_config_valid(config: &Config) {
-    config.keyboard_shortcuts.iter().for_each(|shortcut| {
-        shortcut.actions.iter().for_each(|action| match action {
-            Action::AskChatGPT { .. } => {
-                if config.openai_api_key.trim().is_empty()
-                    || config.openai_api_key.trim() == "sk-..."
-                {
-                    panic!("OpenAI API key is empty or not set in configuration file")
-                }
-            }
-            _ => {}
-        })
-    })
-}
-
-fn init_config_file() {
-    save_config_to_file(&Config {
-        openai_api_key: "sk-...".to_string(),
-        keyboard_shortcuts: vec![
-            //
-            //
-            //
-            Shortcut::new(
-                "Set the variables `city1` and `city2` to `Bordeaux` and `Lyon` respectively, \
-                then check if they are equal. If they are, print `If was true!` to the console, else print `If was false!`.",
-                vec![KeyboardKey(KeybdKey::DKey)],
-                vec![
-                    Action::Debug,
-                    Action::SetVariable {
-                        name: "city1".to_string(),
-                        value: "Bordeaux".to_string(),
-                    },
-                    Action::SetVariable {
-                        name: "city2".to_string(),
-                        value: "Lyon".to_string(),
-                    },
-                    Action::new_if_else_relative(
-                        SerializedComparison {
-                            operation: StringOperator::Equals.to_string(),
-                            a: "{{city1}}".to_string(),
-                            b: "{{city2}}".to_string(),
+fn init_config_file(config_path: &Path) -> anyhow::Result<()> {
+    save_config_to_file(
+        &Config {
+            version: CONFIG_VERSION,
+            openai_api_key: Secret::Plain("sk-...".to_string()),
+            keyboard_shortcuts: vec![
+                //
+                //
+                //
+                Shortcut::new(
+                    "Set the variables `city1` and `city2` to `Bordeaux` and `Lyon` respectively, \
+                    then check if they are equal. If they are, print `If was true!` to the console, else print `If was false!`.",
+                    vec![KeyboardKey::Key(KeybdKey::DKey)],
+                    vec![
+                        Action::Debug,
+                        Action::SetVariable {
+                            name: "city1".to_string(),
+                            value: "Bordeaux".to_string(),
+                        },
+                        Action::SetVariable {
+                            name: "city2".to_string(),
+                            value: "Lyon".to_string(),
+                        },
+                        Action::new_if_else_relative(
+                            SerializedComparison {
+                                operation: StringOperator::Equals.to_string(),
+                                a: "{{city1}}".to_string(),
+                                b: "{{city2}}".to_string(),
+                            },
+                            "+1",
+                            "+3",
+                        ),
+                        Action::PrintConsole {
+                            content: "If was true!".to_string(),
+                        },
+                        Action::EndProgram,
+                        Action::PrintConsole {
+                            content: "If was false!".to_string(),
+                        },
+                    ],
+                ),
+                //
+                //
+                //
+                Shortcut::new(
+                    "Read the clipboard, print debug infos to \
+                    console and show a dialog box with the clipboard content",
+                    vec![
+                        KeyboardKey::Ctrl,
+                        KeyboardKey::Key(KeybdKey::BKey),
+                    ],
+                    vec![
+                        Action::ReadClipboard,
+                        Action::Debug,
+                        Action::ShowDialog {
+                            title: "Hello World!".to_string(),
+                            body: "{{input}}".to_string(),
+                        },
+                    ],
+                ),
+                //
+                //
+                //
+                Shortcut::new(
+                    "Print `Loop iteration 0` to `Loop iteration 4` in the console",
+                    vec![
+                        KeyboardKey::Ctrl,
+                        KeyboardKey::Key(KeybdKey::MKey),
+                    ],
+                    vec![
+                        Action::SetVariable {
+                            name: "i".to_string(),
+                            value: "0".to_string(),
+                        },
+                        Action::PrintConsole {
+                            content: "Loop iteration {{i}}".to_string(),
+                        },
+                        Action::IncrementVariable {
+                            name: "i".to_string(),
+                            amount: "1".to_string(),
                         },
-                        "+1",
-                        "+3",
-                    ),
-                    Action::PrintConsole {
-                        content: "If was true!".to_string(),
-                    },
-                    Action::EndProgram,
-                    Action::PrintConsole {
-                        content: "If was false!".to_string(),
-                    },
-                ],
-            ),
-            //
-            //
-            //
-            Shortcut::new(
-                "Read the clipboard, print debug infos to \
-                console and show a dialog box with the clipboard content",
-                vec![
-                    KeyboardKey(KeybdKey::LControlKey),
-                    KeyboardKey(KeybdKey::BKey),
-                ],
-                vec![
-                    Action::ReadClipboard,
-                    Action::Debug,
-                    Action::ShowDialog {
-                        title: "Hello World!".to_string(),
-                        body: "{{input}}".to_string(),
-                    },
-                ],
-            ),
-            //
-            //
-            //
-            Shortcut::new(
-                "Print `Loop iteration 0` to `Loop iteration 4` in the console",
-                vec![
-                    KeyboardKey(KeybdKey::LControlKey),
-                    KeyboardKey(KeybdKey::MKey),
-                ],
-                vec![
-                    Action::SetVariable {
-                        name: "i".to_string(),
-                        value: "0".to_string(),
-                    },
-                    Action::PrintConsole {
-                        content: "Loop iteration {{i}}".to_string(),
-                    },
-                    Action::IncrementVariable {
-                        name: "i".to_string(),
-                        amount: "1".to_string(),
-                    },
-                    Action::new_if_else_relative(
-                        SerializedComparison {
-                            operation: NumberOperator::LessThan.to_string(),
-                            a: "{{i}}".to_string(),
-                            b: "5".to_string(),
+                        Action::new_if_else_relative(
+                            SerializedComparison {
+                                operation: NumberOperator::LessThan.to_string(),
+                                a: "{{i}}".to_string(),
+                                b: "5".to_string(),
+                            },
+                            "-2",
+                            "+1",
+                        ),
+                        Action::PrintConsole {
+                            content: "End of the loop!".to_string(),
                         },
-                        "-2",
-                        "+1",
-                    ),
-                    Action::PrintConsole {
-                        content: "End of the loop!".to_string(),
-                    },
-                ],
-            ),
-        ],
-    })
-    .unwrap();
-}
-
-fn get_config_path() -> PathBuf {
-    env::current_dir().unwrap().join(CONFIG_FILE_NAME)
+                    ],
+                ),
+            ],
+            ..Default::default()
+        },
+        config_path,
+    )
 }
@@ -0,0 +1,411 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::hotkey::{PhysicalKey, Shortcut, Trigger};
+use crate::input::InputBackend;
+use crate::keys::format_key_sequence;
+use crate::mode::current_mode;
+use crate::{get_sorted_keys, is_combo_pressed, Config};
+
+/// Find the shortcuts of the current mode matching the pressed keys and run them.
+///
+/// A shortcut can be a sequence of key combos (e.g. `Ctrl+K` then `Ctrl+C`). While the beginning
+/// of a sequence is pressed, the dispatcher waits for the next combo. A shortcut sharing its
+/// combos with the beginning of a longer sequence only runs once no other combo was pressed
+/// before the timeout.
+///
+/// Once its keys are pressed, a shortcut runs depending on its `trigger`: right away, when the
+/// keys are released, held long enough or pressed twice. The key repeats of a key held down do
+/// not trigger the shortcuts again, unless they `repeat`.
+pub struct Dispatcher {
+    config: Arc<Config>,
+    backend: Arc<dyn InputBackend>,
+    runner: Arc<dyn ShortcutRunner>,
+    state: Mutex<SequenceState>,
+    /// Time of the last press of the `double_tap` shortcuts, by mode and shortcut index.
+    last_taps: Mutex<HashMap<(String, usize), Instant>>,
+    /// Time of the last run of the shortcuts with a `cooldown_ms`, by mode and shortcut index.
+    last_runs: Mutex<HashMap<(String, usize), Instant>>,
+    /// Keys held down since they triggered a shortcut, their key repeats are ignored until they
+    /// are released.
+    held_keys: Mutex<HashSet<PhysicalKey>>,
+}
+
+/// Runs the shortcuts picked by the dispatcher, usually an `Executor`.
+pub trait ShortcutRunner: Send + Sync {
+    /// Run a shortcut, starting with `input` as `{{input}}`.
+    fn run_with_input(&self, shortcut: &Shortcut, config: &Arc<Config>, input: &str);
+
+    /// Run a shortcut, starting with an empty input.
+    fn run(&self, shortcut: &Shortcut, config: &Arc<Config>) {
+        self.run_with_input(shortcut, config, "");
+    }
+
+    /// Cancel every shortcut run in flight, when the `abort_keys` are pressed.
+    fn cancel_all(&self);
+}
+
+/// Interval to check if the held keys are still pressed.
+const RELEASE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Sequence of key combos being pressed.
+#[derive(Default)]
+struct SequenceState {
+    /// Mode the sequence was started in.
+    mode: String,
+    /// Number of combos of the sequence already pressed, `0` if no sequence is pending.
+    depth: usize,
+    /// Index of the shortcuts of the mode still matching the sequence.
+    candidates: Vec<usize>,
+    /// Index of the shortcuts of the mode whose whole sequence was pressed, run on timeout.
+    completed: Vec<usize>,
+    /// Incremented on every change, to ignore the timeouts of previous sequences.
+    generation: u64,
+}
+
+impl SequenceState {
+    fn is_pending(&self) -> bool {
+        self.depth > 0
+    }
+
+    /// Forget the pending sequence, returns the shortcuts that were waiting for the timeout.
+    fn reset(&mut self) -> Vec<usize> {
+        self.depth = 0;
+        self.candidates.clear();
+        self.generation += 1;
+        std::mem::take(&mut self.completed)
+    }
+
+    /// Get the shortcuts whose next combo is pressed.
+    fn matching(
+        &self,
+        backend: &dyn InputBackend,
+        shortcuts: &[Shortcut],
+        pressed_key: PhysicalKey,
+    ) -> Vec<usize> {
+        let is_next_combo_pressed = |i: &usize| {
+            let shortcut = &shortcuts[*i];
+            shortcut
+                .key_sequence()
+                .get(self.depth)
+                .is_some_and(|combo| {
+                    is_combo_pressed(backend, combo, pressed_key, shortcut.exact_match)
+                })
+        };
+        if self.is_pending() {
+            self.candidates
+                .iter()
+                .copied()
+                .filter(is_next_combo_pressed)
+                .collect()
+        } else {
+            (0..shortcuts.len()).filter(is_next_combo_pressed).collect()
+        }
+    }
+}
+
+impl Dispatcher {
+    /// Create a dispatcher handing the shortcuts to run to `runner`, usually an `Executor`.
+    pub fn new(
+        config: Arc<Config>,
+        backend: Arc<dyn InputBackend>,
+        runner: Arc<dyn ShortcutRunner>,
+    ) -> Arc<Dispatcher> {
+        Arc::new(Dispatcher {
+            config,
+            backend,
+            runner,
+            state: Mutex::new(SequenceState::default()),
+            last_taps: Mutex::new(HashMap::new()),
+            last_runs: Mutex::new(HashMap::new()),
+            held_keys: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// Get the keys to bind to detect every combo of every shortcut, in every mode, and the
+    /// `abort_keys`.
+    pub fn trigger_keys(&self) -> HashSet<PhysicalKey> {
+        self.config
+            .all_shortcuts()
+            .flat_map(|shortcut| shortcut.key_sequence())
+            .chain([self.config.abort_keys.as_slice()])
+            .filter_map(|combo| get_sorted_keys(combo).first().copied())
+            .flat_map(|key| key.physical_keys())
+            .collect()
+    }
+
+    /// Bind the trigger keys on the input backend, returns the keys that were bound.
+    pub fn bind(self: &Arc<Self>) -> Vec<PhysicalKey> {
+        let trigger_keys = self.trigger_keys();
+        for key in &trigger_keys {
+            let key = *key;
+            let dispatcher = self.clone();
+            self.backend
+                .bind(key, Arc::new(move || dispatcher.on_key_pressed(key)));
+        }
+        trigger_keys.into_iter().collect()
+    }
+
+    pub fn on_key_pressed(self: &Arc<Self>, pressed_key: PhysicalKey) {
+        let mode = current_mode();
+        if self.held_keys.lock().unwrap().contains(&pressed_key) {
+            self.on_key_repeat(&mode, pressed_key);
+            return;
+        }
+
+        if is_combo_pressed(
+            self.backend.as_ref(),
+            &self.config.abort_keys,
+            pressed_key,
+            false,
+        ) {
+            self.hold_until_released(pressed_key);
+            self.state.lock().unwrap().reset();
+            self.runner.cancel_all();
+            return;
+        }
+
+        let shortcuts = self.config.shortcuts_for_mode(&mode);
+
+        let mut to_run = Vec::new();
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.is_pending() && state.mode != mode {
+                state.reset();
+            }
+
+            let mut matching = state.matching(self.backend.as_ref(), shortcuts, pressed_key);
+            if matching.is_empty() && state.is_pending() {
+                // The combo does not continue the sequence, run the shortcuts that were waiting
+                // for the timeout and try the combo as the start of a new sequence
+                to_run = state.reset();
+                if to_run.is_empty() {
+                    println!("Key sequence cancelled");
+                }
+                matching = state.matching(self.backend.as_ref(), shortcuts, pressed_key);
+            }
+
+            if !matching.is_empty() {
+                self.hold_until_released(pressed_key);
+            }
+
+            let depth = state.depth + 1;
+            let (completed, longer): (Vec<usize>, Vec<usize>) = matching
+                .into_iter()
+                .partition(|i| shortcuts[*i].key_sequence().len() == depth);
+
+            if longer.is_empty() {
+                state.reset();
+                to_run.extend(completed);
+            } else {
+                state.mode = mode.clone();
+                state.depth = depth;
+                state.candidates = longer;
+                state.completed = completed;
+                state.generation += 1;
+                print_pending_hint(shortcuts, &state);
+                self.schedule_timeout(state.generation);
+            }
+        }
+
+        self.fire(&mode, to_run);
+    }
+
+    /// Run the shortcuts that `repeat` when the key is repeated while held down.
+    fn on_key_repeat(&self, mode: &str, pressed_key: PhysicalKey) {
+        if self.state.lock().unwrap().is_pending() {
+            return;
+        }
+
+        let shortcuts = self.config.shortcuts_for_mode(mode);
+        for (i, shortcut) in shortcuts.iter().enumerate() {
+            let is_repeated = shortcut.repeat
+                && shortcut.trigger == Trigger::Press
+                && shortcut.sequence.is_empty()
+                && is_combo_pressed(
+                    self.backend.as_ref(),
+                    &shortcut.keys,
+                    pressed_key,
+                    shortcut.exact_match,
+                );
+            if is_repeated {
+                self.run(mode, i);
+            }
+        }
+    }
+
+    /// Ignore the key repeats of `key` until it is released.
+    fn hold_until_released(self: &Arc<Self>, key: PhysicalKey) {
+        self.held_keys.lock().unwrap().insert(key);
+
+        let dispatcher = self.clone();
+        thread::spawn(move || {
+            while dispatcher.backend.is_pressed(key) {
+                thread::sleep(RELEASE_POLL_INTERVAL);
+            }
+            dispatcher.held_keys.lock().unwrap().remove(&key);
+        });
+    }
+
+    /// End the pending sequence after the timeout, if no other combo was pressed meanwhile.
+    fn schedule_timeout(self: &Arc<Self>, generation: u64) {
+        let dispatcher = self.clone();
+        let timeout = self.config.sequence_timeout();
+        thread::spawn(move || {
+            thread::sleep(timeout);
+            dispatcher.on_timeout(generation);
+        });
+    }
+
+    fn on_timeout(self: &Arc<Self>, generation: u64) {
+        let mode = current_mode();
+        let to_run = {
+            let mut state = self.state.lock().unwrap();
+            if state.generation != generation {
+                return;
+            }
+            let to_run = state.reset();
+            if to_run.is_empty() {
+                println!("Key sequence timed out");
+            }
+            if state.mode != mode {
+                return;
+            }
+            to_run
+        };
+
+        self.fire(&mode, to_run);
+    }
+
+    /// Run the shortcuts whose keys were pressed, depending on their `trigger`.
+    fn fire(self: &Arc<Self>, mode: &str, triggered: Vec<usize>) {
+        let shortcuts = self.config.shortcuts_for_mode(mode);
+        let (on_release, on_press): (Vec<usize>, Vec<usize>) =
+            triggered.into_iter().partition(|i| {
+                matches!(
+                    shortcuts[*i].trigger,
+                    Trigger::Release | Trigger::Hold { .. }
+                )
+            });
+
+        // Start watching the keys before running anything, to measure the hold duration
+        if !on_release.is_empty() {
+            self.watch_release(mode, on_release);
+        }
+
+        for i in on_press {
+            match shortcuts[i].trigger {
+                Trigger::DoubleTap { within_ms } => {
+                    if self.is_double_tap(mode, i, Duration::from_millis(within_ms)) {
+                        self.run(mode, i);
+                    }
+                }
+                _ => self.run(mode, i),
+            }
+        }
+    }
+
+    /// Run a shortcut, unless it already ran less than its `cooldown_ms` ago.
+    fn run(&self, mode: &str, shortcut_index: usize) {
+        let shortcut = &self.config.shortcuts_for_mode(mode)[shortcut_index];
+        if let Some(cooldown_ms) = shortcut.cooldown_ms {
+            let mut last_runs = self.last_runs.lock().unwrap();
+            let key = (mode.to_string(), shortcut_index);
+            let cooldown = Duration::from_millis(cooldown_ms);
+            if let Some(elapsed) = last_runs.get(&key).map(Instant::elapsed) {
+                if elapsed < cooldown {
+                    println!(
+                        "Ignored {} - {}, in cooldown for another {}ms",
+                        shortcut.format_keys(),
+                        shortcut.description,
+                        (cooldown - elapsed).as_millis()
+                    );
+                    return;
+                }
+            }
+            last_runs.insert(key, Instant::now());
+        }
+
+        self.runner.run(shortcut, &self.config);
+    }
+
+    /// Record a press of a `double_tap` shortcut, returns whether it is the second press.
+    fn is_double_tap(&self, mode: &str, shortcut_index: usize, within: Duration) -> bool {
+        let mut last_taps = self.last_taps.lock().unwrap();
+        let key = (mode.to_string(), shortcut_index);
+        match last_taps.remove(&key) {
+            Some(last_tap) if last_tap.elapsed() <= within => true,
+            _ => {
+                last_taps.insert(key, Instant::now());
+                false
+            }
+        }
+    }
+
+    /// Run the `hold` shortcuts once their keys are held long enough, and the `release`
+    /// shortcuts once their keys are released.
+    ///
+    /// A `release` shortcut does not run if a `hold` shortcut ran during the same key press, so
+    /// the same keys can do something different when tapped or held.
+    fn watch_release(self: &Arc<Self>, mode: &str, mut pending: Vec<usize>) {
+        let dispatcher = self.clone();
+        let mode = mode.to_string();
+        thread::spawn(move || {
+            let shortcuts = dispatcher.config.shortcuts_for_mode(&mode);
+            let pressed_at = Instant::now();
+            let mut hold_ran = false;
+
+            while !pending.is_empty() {
+                thread::sleep(RELEASE_POLL_INTERVAL);
+                let held_duration = pressed_at.elapsed();
+
+                let mut to_run = Vec::new();
+                pending.retain(|i| {
+                    let shortcut = &shortcuts[*i];
+                    let is_held = shortcut.key_sequence().last().is_some_and(|combo| {
+                        combo
+                            .iter()
+                            .all(|key| key.is_pressed(dispatcher.backend.as_ref()))
+                    });
+                    match shortcut.trigger {
+                        Trigger::Hold { min_duration_ms } => {
+                            if is_held && held_duration >= Duration::from_millis(min_duration_ms) {
+                                hold_ran = true;
+                                to_run.push(*i);
+                                return false;
+                            }
+                            is_held
+                        }
+                        _ => {
+                            if !is_held && !hold_ran {
+                                to_run.push(*i);
+                            }
+                            is_held
+                        }
+                    }
+                });
+
+                for i in to_run {
+                    dispatcher.run(&mode, i);
+                }
+            }
+        });
+    }
+}
+
+/// Print the shortcuts waiting for the next combo of the sequence.
+fn print_pending_hint(shortcuts: &[Shortcut], state: &SequenceState) {
+    println!("Key sequence pending, waiting for the next combo:");
+    for i in &state.candidates {
+        let shortcut = &shortcuts[*i];
+        let sequence = shortcut.key_sequence();
+        println!(
+            "  {} then {} - {}",
+            format_key_sequence(&sequence[..state.depth]),
+            format_key_sequence(&sequence[state.depth..]),
+            shortcut.description
+        );
+    }
+}
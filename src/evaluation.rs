@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use anyhow::Context;
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
@@ -59,28 +60,28 @@ impl Comparison {
             "string_is_empty" => Ok(Comparison::String(StringComparison::IsEmpty { a })),
             "string_is_not_empty" => Ok(Comparison::String(StringComparison::IsNotEmpty { a })),
             "==" => Ok(Comparison::Number(NumberComparison::Equal {
-                a: a.parse().unwrap(),
-                b: b.parse().unwrap(),
+                a: parse_number(&a)?,
+                b: parse_number(&b)?,
             })),
             "!=" => Ok(Comparison::Number(NumberComparison::NotEqual {
-                a: a.parse().unwrap(),
-                b: b.parse().unwrap(),
+                a: parse_number(&a)?,
+                b: parse_number(&b)?,
             })),
             ">" => Ok(Comparison::Number(NumberComparison::GreaterThan {
-                a: a.parse().unwrap(),
-                b: b.parse().unwrap(),
+                a: parse_number(&a)?,
+                b: parse_number(&b)?,
             })),
             "<" => Ok(Comparison::Number(NumberComparison::LessThan {
-                a: a.parse().unwrap(),
-                b: b.parse().unwrap(),
+                a: parse_number(&a)?,
+                b: parse_number(&b)?,
             })),
             ">=" => Ok(Comparison::Number(NumberComparison::GreaterThanOrEqual {
-                a: a.parse().unwrap(),
-                b: b.parse().unwrap(),
+                a: parse_number(&a)?,
+                b: parse_number(&b)?,
             })),
             "<=" => Ok(Comparison::Number(NumberComparison::LessThanOrEqual {
-                a: a.parse().unwrap(),
-                b: b.parse().unwrap(),
+                a: parse_number(&a)?,
+                b: parse_number(&b)?,
             })),
             _ => Err(anyhow::anyhow!("Unknown operation: {}", operation)),
         }
@@ -186,6 +187,12 @@ impl Comparison {
     }
 }
 
+fn parse_number(value: &str) -> anyhow::Result<f64> {
+    value
+        .parse::<f64>()
+        .with_context(|| format!("`{value}` is not a valid number"))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "operation")]
 #[derive(TS)]
@@ -217,16 +224,15 @@ pub enum NumberOperator {
 }
 
 impl NumberOperator {
-    #[allow(dead_code)]
-    pub fn from(str: &str) -> NumberOperator {
+    pub fn from(str: &str) -> Option<NumberOperator> {
         match str {
-            "==" => NumberOperator::Equal,
-            "!=" => NumberOperator::NotEqual,
-            "<" => NumberOperator::LessThan,
-            "<=" => NumberOperator::LessThanOrEqual,
-            ">" => NumberOperator::GreaterThan,
-            ">=" => NumberOperator::GreaterThanOrEqual,
-            _ => panic!("Invalid operator"),
+            "==" => Some(NumberOperator::Equal),
+            "!=" => Some(NumberOperator::NotEqual),
+            "<" => Some(NumberOperator::LessThan),
+            "<=" => Some(NumberOperator::LessThanOrEqual),
+            ">" => Some(NumberOperator::GreaterThan),
+            ">=" => Some(NumberOperator::GreaterThanOrEqual),
+            _ => None,
         }
     }
 
@@ -272,18 +278,17 @@ pub enum StringOperator {
 }
 
 impl StringOperator {
-    #[allow(dead_code)]
-    pub fn from(str: &str) -> StringOperator {
+    pub fn from(str: &str) -> Option<StringOperator> {
         match str {
-            "string_equals" => StringOperator::Equals,
-            "string_not_equals" => StringOperator::NotEquals,
-            "string_contains" => StringOperator::Contains,
-            "string_not_contains" => StringOperator::NotContains,
-            "string_starts_with" => StringOperator::StartsWith,
-            "string_ends_with" => StringOperator::EndsWith,
-            "string_is_empty" => StringOperator::IsEmpty,
-            "string_is_not_empty" => StringOperator::IsNotEmpty,
-            _ => panic!("Invalid operator"),
+            "string_equals" => Some(StringOperator::Equals),
+            "string_not_equals" => Some(StringOperator::NotEquals),
+            "string_contains" => Some(StringOperator::Contains),
+            "string_not_contains" => Some(StringOperator::NotContains),
+            "string_starts_with" => Some(StringOperator::StartsWith),
+            "string_ends_with" => Some(StringOperator::EndsWith),
+            "string_is_empty" => Some(StringOperator::IsEmpty),
+            "string_is_not_empty" => Some(StringOperator::IsNotEmpty),
+            _ => None,
         }
     }
 
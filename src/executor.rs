@@ -0,0 +1,321 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::anyhow;
+
+use crate::dispatch::ShortcutRunner;
+use crate::hotkey::{Concurrency, Shortcut, TriggerResult};
+use crate::Config;
+
+/// Run the shortcuts on a pool of worker threads, so a slow shortcut does not block the keyboard
+/// events nor the other shortcuts.
+///
+/// The `concurrency` of a shortcut decides what happens when it is triggered while it is still
+/// running. At most `max_concurrent_runs` shortcuts run at the same time, the others wait for a
+/// free worker in the order they were triggered.
+pub struct Executor {
+    run: RunFn,
+    state: Mutex<ExecutorState>,
+    /// Notified when a run is ready to start or is finished.
+    changed: Condvar,
+}
+
+/// Run the actions of a shortcut with an input and initial variables, stopping early when the
+/// token is cancelled.
+pub type RunFn = Arc<
+    dyn Fn(
+            &Shortcut,
+            &Arc<Config>,
+            &str,
+            HashMap<String, String>,
+            &CancelToken,
+        ) -> anyhow::Result<TriggerResult>
+        + Send
+        + Sync,
+>;
+
+#[derive(Default)]
+struct ExecutorState {
+    /// Runs ready to start, picked by the first free worker.
+    ready: VecDeque<Run>,
+    /// Number of runs in progress.
+    running: usize,
+    /// Maximum number of runs in progress, the extra workers stay idle.
+    max_running: usize,
+    /// Number of worker threads started.
+    workers: usize,
+    /// Runs of the shortcuts that are ready or in progress, by shortcut key.
+    shortcuts: HashMap<String, ShortcutRuns>,
+}
+
+impl ExecutorState {
+    fn is_idle(&self) -> bool {
+        self.running == 0 && self.ready.is_empty()
+    }
+}
+
+#[derive(Default)]
+struct ShortcutRuns {
+    /// Cancel tokens of the runs ready or in progress.
+    active: Vec<CancelToken>,
+    /// Runs waiting for the current run of the shortcut to finish, with the `queue` concurrency.
+    queued: VecDeque<Run>,
+}
+
+struct Run {
+    key: String,
+    shortcut: Shortcut,
+    config: Arc<Config>,
+    input: String,
+    args: HashMap<String, String>,
+    cancel: CancelToken,
+    /// Where to send the result of the run, if someone waits for it.
+    reply: Option<Sender<anyhow::Result<TriggerResult>>>,
+}
+
+impl Executor {
+    /// Start `max_concurrent_runs` workers running the shortcuts with `run`.
+    pub fn new(max_concurrent_runs: usize, run: RunFn) -> Arc<Executor> {
+        let executor = Arc::new(Executor {
+            run,
+            state: Mutex::new(ExecutorState::default()),
+            changed: Condvar::new(),
+        });
+        executor.set_max_concurrent_runs(max_concurrent_runs);
+        executor
+    }
+
+    /// Change the maximum number of runs in progress, e.g. when the configuration is reloaded.
+    /// The runs in progress are not interrupted when lowering it.
+    pub fn set_max_concurrent_runs(self: &Arc<Self>, max_concurrent_runs: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.max_running = max_concurrent_runs.max(1);
+        while state.workers < state.max_running {
+            let executor = self.clone();
+            thread::spawn(move || executor.work());
+            state.workers += 1;
+        }
+        self.changed.notify_all();
+    }
+
+    /// Run a shortcut on a worker with `input` as `{{input}}`, depending on its `concurrency` if
+    /// it is already running.
+    pub fn submit(&self, shortcut: &Shortcut, config: &Arc<Config>, input: &str) {
+        self.submit_run(Run {
+            key: run_key(shortcut, config),
+            shortcut: shortcut.clone(),
+            config: config.clone(),
+            input: input.to_string(),
+            args: HashMap::new(),
+            cancel: CancelToken::default(),
+            reply: None,
+        });
+    }
+
+    /// Run a shortcut on a worker like `submit`, with the `args` variables overriding its initial
+    /// variables, and wait for the result of the run.
+    pub fn trigger(
+        &self,
+        shortcut: &Shortcut,
+        config: &Arc<Config>,
+        input: &str,
+        args: HashMap<String, String>,
+    ) -> anyhow::Result<TriggerResult> {
+        let (tx, rx) = mpsc::channel();
+        self.submit_run(Run {
+            key: run_key(shortcut, config),
+            shortcut: shortcut.clone(),
+            config: config.clone(),
+            input: input.to_string(),
+            args,
+            cancel: CancelToken::default(),
+            reply: Some(tx),
+        });
+        // The reply is dropped without a result when a queued run is cancelled
+        rx.recv().unwrap_or_else(|_| Err(Cancelled.into()))
+    }
+
+    fn submit_run(&self, run: Run) {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let runs = state.shortcuts.entry(run.key.clone()).or_default();
+        if !runs.active.is_empty() {
+            match run.shortcut.concurrency {
+                Concurrency::Parallel => {}
+                Concurrency::Queue => {
+                    runs.queued.push_back(run);
+                    return;
+                }
+                Concurrency::DropIfRunning => {
+                    println!("Ignored {}, it is already running", run.key);
+                    if let Some(reply) = run.reply {
+                        reply
+                            .send(Err(anyhow!("Shortcut {} is already running", run.key)))
+                            .ok();
+                    }
+                    return;
+                }
+                Concurrency::Restart => {
+                    println!("Restarting {}", run.key);
+                    runs.active.iter().for_each(CancelToken::cancel);
+                }
+            }
+        }
+        runs.active.push(run.cancel.clone());
+        state.ready.push_back(run);
+        self.changed.notify_all();
+    }
+
+    /// Block until no shortcut is running or waiting to run.
+    pub fn wait_idle(&self) {
+        let _state = self
+            .changed
+            .wait_while(self.state.lock().unwrap(), |state| !state.is_idle())
+            .unwrap();
+    }
+
+    /// Block until no shortcut is running or waiting to run, at most `timeout`. Returns whether
+    /// the executor is idle.
+    pub fn wait_idle_timeout(&self, timeout: Duration) -> bool {
+        let (_state, result) = self
+            .changed
+            .wait_timeout_while(self.state.lock().unwrap(), timeout, |state| {
+                !state.is_idle()
+            })
+            .unwrap();
+        !result.timed_out()
+    }
+
+    fn work(&self) {
+        loop {
+            let mut run = {
+                let mut state = self
+                    .changed
+                    .wait_while(self.state.lock().unwrap(), |state| {
+                        state.ready.is_empty() || state.running >= state.max_running
+                    })
+                    .unwrap();
+                state.running += 1;
+                state.ready.pop_front().unwrap()
+            };
+
+            // A panicking action must not take the worker down with it
+            let args = std::mem::take(&mut run.args);
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                (self.run)(&run.shortcut, &run.config, &run.input, args, &run.cancel)
+            }))
+            .unwrap_or_else(|_| Err(anyhow!("Shortcut {} panicked", run.key)));
+            if let Some(reply) = run.reply.take() {
+                reply.send(result).ok();
+            }
+            self.finish(run);
+        }
+    }
+
+    /// Forget a finished run and start the next queued run of the shortcut.
+    fn finish(&self, run: Run) {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        state.running -= 1;
+        if let Some(runs) = state.shortcuts.get_mut(&run.key) {
+            runs.active.retain(|cancel| !cancel.is_same(&run.cancel));
+            if let Some(next) = runs.queued.pop_front() {
+                runs.active.push(next.cancel.clone());
+                state.ready.push_back(next);
+            }
+            if runs.active.is_empty() {
+                state.shortcuts.remove(&run.key);
+            }
+        }
+        self.changed.notify_all();
+    }
+}
+
+impl ShortcutRunner for Executor {
+    fn run_with_input(&self, shortcut: &Shortcut, config: &Arc<Config>, input: &str) {
+        self.submit(shortcut, config, input);
+    }
+
+    /// Cancel the runs in progress or ready to start, and forget the queued runs.
+    fn cancel_all(&self) {
+        let mut state = self.state.lock().unwrap();
+        let mut cancelled = 0;
+        for runs in state.shortcuts.values_mut() {
+            runs.active.iter().for_each(CancelToken::cancel);
+            cancelled += runs.active.len() + runs.queued.len();
+            runs.queued.clear();
+        }
+        println!("Aborting, {cancelled} shortcut runs cancelled");
+    }
+}
+
+/// Identify the runs of the same shortcut: its `id`, else its mode and index in the configuration.
+fn run_key(shortcut: &Shortcut, config: &Config) -> String {
+    if let Some(id) = &shortcut.id {
+        return format!("`{id}`");
+    }
+    match config.shortcut_position(shortcut) {
+        Some((mode, index)) => format!("shortcut {index} of mode `{mode}`"),
+        // Not part of the configuration, only used by the tests
+        None => format!("{} - {}", shortcut.format_keys(), shortcut.description),
+    }
+}
+
+/// Cancels a shortcut run, the cancellation is checked between its actions and interrupts the
+/// actions waiting for something (e.g. `sleep` or `spawn`).
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<(Mutex<bool>, Condvar)>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        let (cancelled, changed) = &*self.0;
+        *cancelled.lock().unwrap() = true;
+        changed.notify_all();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        let (cancelled, _) = &*self.0;
+        *cancelled.lock().unwrap()
+    }
+
+    /// Fail with `Cancelled` if the run was cancelled.
+    pub fn check(&self) -> anyhow::Result<()> {
+        if self.is_cancelled() {
+            return Err(Cancelled.into());
+        }
+        Ok(())
+    }
+
+    /// Wait for `duration`, fails with `Cancelled` as soon as the run is cancelled.
+    pub fn sleep(&self, duration: Duration) -> anyhow::Result<()> {
+        let (cancelled, changed) = &*self.0;
+        let (cancelled, _) = changed
+            .wait_timeout_while(cancelled.lock().unwrap(), duration, |cancelled| !*cancelled)
+            .unwrap();
+        if *cancelled {
+            return Err(Cancelled.into());
+        }
+        Ok(())
+    }
+
+    fn is_same(&self, other: &CancelToken) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Error of a shortcut run that was cancelled before its end.
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Shortcut run cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
@@ -1,53 +1,198 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
 
 use anyhow::anyhow;
-pub use inputbot::KeybdKey;
+pub use inputbot::{KeybdKey, MouseButton};
 use rand::Rng;
+use schemars::JsonSchema;
+use serde::ser::{SerializeMap, Serializer};
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
 use crate::actions::Action;
+use crate::executor::CancelToken;
+use crate::input::InputBackend;
+use crate::keys::{
+    deserialize_key_combo, deserialize_key_sequence, format_key_sequence, key_combo_schema,
+    key_sequence_schema,
+};
+use crate::mode::{current_mode, MODE_VARIABLE};
+use crate::schedule::Schedule;
+use crate::watch::FileWatch;
 use crate::Config;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A keyboard shortcut and the actions it triggers.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Shortcut {
+    /// Unique identifier of the shortcut. A shortcut from a file merged later with the same id
+    /// replaces this one (see `include`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Description of what the shortcut does.
     #[serde(default)]
     pub description: String,
+    /// Disable the shortcut, useful to disable a shortcut from an included file by its `id`.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub disabled: bool,
+    /// Keyboard keys and mouse buttons to all be pressed at the same time to trigger the actions,
+    /// e.g. `Ctrl+Shift+B` or `["LControlKey", "LShiftKey", "BKey"]`.
+    #[serde(default, deserialize_with = "deserialize_key_combo")]
+    #[schemars(schema_with = "key_combo_schema")]
     pub keys: Vec<KeyboardKey>,
+    /// Key combos to press one after the other to trigger the actions, e.g. `Ctrl+K Ctrl+C`.
+    /// Use instead of `keys`.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_key_sequence",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[schemars(schema_with = "key_sequence_schema")]
+    pub sequence: Vec<Vec<KeyboardKey>>,
+    /// Run the actions on a schedule, e.g. `{"every_ms": 60000}` or `{"cron": "0 9 * * *"}`.
+    /// Use alongside or instead of `keys`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<Schedule>,
+    /// Run the actions when files change, with the path of the changed file as `{{input}}`, e.g.
+    /// `{"path": "~/Pictures", "glob": "*.png"}`. Use alongside or instead of `keys`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watch: Option<FileWatch>,
+    /// Only trigger if no other modifier than the ones of the keys is pressed, e.g. `Ctrl+B` does
+    /// not trigger when pressing `Ctrl+Shift+B`.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub exact_match: bool,
+    /// When to run the actions once the keys are pressed, or when Shortcut Hero starts or exits.
+    /// Default is `press`.
+    #[serde(default, skip_serializing_if = "Trigger::is_press")]
+    pub trigger: Trigger,
+    /// Run the actions again on every key repeat while the keys are held down. By default, the
+    /// keys must be released before the shortcut can trigger again.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub repeat: bool,
+    /// Minimum time in milliseconds between two runs of the shortcut, the triggers in between are
+    /// ignored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooldown_ms: Option<u64>,
+    /// What to do when the shortcut is triggered while it is still running. Default is `queue`.
+    #[serde(default, skip_serializing_if = "Concurrency::is_queue")]
+    pub concurrency: Concurrency,
+    /// Variables available in the actions of this shortcut, overriding the global `variables`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, String>,
+    /// Actions to run, in order, when the shortcut is triggered.
+    #[serde(default)]
     pub actions: Vec<Action>,
 }
 
 impl Shortcut {
     pub fn new(description: &str, keys: Vec<KeyboardKey>, actions: Vec<Action>) -> Self {
         Shortcut {
+            id: None,
             description: description.to_string(),
+            disabled: false,
             keys,
+            sequence: Vec::new(),
+            schedule: None,
+            watch: None,
+            exact_match: false,
+            trigger: Trigger::Press,
+            repeat: false,
+            cooldown_ms: None,
+            concurrency: Concurrency::Queue,
+            variables: BTreeMap::new(),
             actions,
         }
     }
 
     pub fn new_no_description(keys: Vec<KeyboardKey>, actions: Vec<Action>) -> Self {
         Shortcut {
+            id: None,
             description: "".to_string(),
+            disabled: false,
             keys,
+            sequence: Vec::new(),
+            schedule: None,
+            watch: None,
+            exact_match: false,
+            trigger: Trigger::Press,
+            repeat: false,
+            cooldown_ms: None,
+            concurrency: Concurrency::Queue,
+            variables: BTreeMap::new(),
             actions,
         }
     }
 
-    pub fn trigger(&self, config: &Config) -> anyhow::Result<Vec<String>> {
+    /// Get the key combos to press one after the other to trigger the shortcut, `keys` is a
+    /// sequence of a single combo.
+    pub fn key_sequence(&self) -> Vec<&[KeyboardKey]> {
+        if !self.sequence.is_empty() {
+            self.sequence.iter().map(Vec::as_slice).collect()
+        } else if !self.keys.is_empty() {
+            vec![self.keys.as_slice()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Whether something else than a `call_shortcut` action can run the shortcut: its keys, its
+    /// schedule, its watched files or its lifecycle trigger.
+    pub fn has_trigger(&self) -> bool {
+        !self.key_sequence().is_empty()
+            || self.schedule.is_some()
+            || self.watch.is_some()
+            || self.trigger.is_lifecycle()
+    }
+
+    /// Get the keys of the shortcut in human-readable form, e.g. `Ctrl+Shift+B` or
+    /// `Ctrl+K Ctrl+C` for a sequence.
+    pub fn format_keys(&self) -> String {
+        format_key_sequence(&self.key_sequence())
+    }
+
+    /// Get the variables to start the actions with: the global variables, overridden by the
+    /// shortcut variables, and the current mode.
+    fn initial_variables(&self, config: &Config) -> HashMap<String, String> {
+        let mut variables: HashMap<String, String> = config
+            .variables
+            .iter()
+            .chain(self.variables.iter())
+            .map(|(name, value)| (name.to_lowercase(), value.clone()))
+            .collect();
+        variables.insert(MODE_VARIABLE.to_string(), current_mode());
+        variables
+    }
+
+    /// Run the actions of the shortcut, starting with an empty input.
+    pub fn trigger(&self, config: &Config) -> anyhow::Result<TriggerResult> {
+        self.trigger_with(config, &TriggerContext::default(), "", HashMap::new())
+    }
+
+    /// Run the actions of the shortcut with the given input, the `args` variables override the
+    /// initial variables of the shortcut. Variables names are case-insensitive.
+    pub fn trigger_with(
+        &self,
+        config: &Config,
+        context: &TriggerContext,
+        input_str: &str,
+        args: HashMap<String, String>,
+    ) -> anyhow::Result<TriggerResult> {
         let trigger_id: u32 = rand::thread_rng().gen();
 
-        let mut variables: HashMap<String, String> = HashMap::new();
-        let mut input_str = "".to_string();
+        let mut variables = self.initial_variables(config);
+        variables.extend(
+            args.into_iter()
+                .map(|(name, value)| (name.to_lowercase(), value)),
+        );
+        let mut input_str = input_str.to_string();
 
         let mut full_actions_result: Vec<String> = Vec::new();
         let mut i_action = 0;
         while i_action < self.actions.len() {
+            context.cancel.check()?;
             let mut used_go_to = false;
 
             let action = &self.actions[i_action];
-            let shortcut_result = action.run(config, &input_str, &mut variables)?;
+            let shortcut_result = action.run(config, context, &input_str, &mut variables)?;
 
             match shortcut_result {
                 ShortcutResult::Success(output) => {
@@ -119,7 +264,146 @@ impl Shortcut {
                 i_action += 1;
             }
         }
-        Ok(full_actions_result)
+        Ok(TriggerResult {
+            output: input_str,
+            variables,
+            actions_result: full_actions_result,
+        })
+    }
+}
+
+/// Maximum depth of nested `call_shortcut` actions, to stop infinite recursions.
+pub const MAX_CALL_DEPTH: usize = 32;
+
+/// Context of a shortcut run, passed down to nested shortcut calls.
+#[derive(Debug, Clone, Default)]
+pub struct TriggerContext {
+    /// Number of `call_shortcut` actions the run is nested in.
+    pub depth: usize,
+    /// Cancels the run and its nested shortcut calls.
+    pub cancel: CancelToken,
+}
+
+impl TriggerContext {
+    /// Get the context of a shortcut called from this run.
+    pub fn nested(&self) -> anyhow::Result<TriggerContext> {
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(anyhow!(
+                "Maximum call depth of {MAX_CALL_DEPTH} reached, is a shortcut calling itself \
+                 endlessly?"
+            ));
+        }
+        Ok(TriggerContext {
+            depth: self.depth + 1,
+            cancel: self.cancel.clone(),
+        })
+    }
+}
+
+/// The result of a shortcut run.
+#[derive(Debug, Clone)]
+pub struct TriggerResult {
+    /// Output of the last action.
+    pub output: String,
+    /// Variables at the end of the run.
+    pub variables: HashMap<String, String>,
+    /// Log of every action run and its output.
+    pub actions_result: Vec<String>,
+}
+
+pub(crate) fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// When to run the actions of a shortcut once its keys are pressed, or without keys when Shortcut
+/// Hero starts or exits.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Trigger {
+    /// When the keys are pressed.
+    #[default]
+    Press,
+    /// When the keys are released. Does not run if a `hold` shortcut with the same keys ran
+    /// while they were held.
+    Release,
+    /// When the keys are held down for a minimum duration.
+    Hold {
+        /// Minimum duration to hold the keys, in milliseconds.
+        min_duration_ms: u64,
+    },
+    /// When the keys are pressed twice in a row.
+    DoubleTap {
+        /// Maximum delay between the two presses, in milliseconds.
+        within_ms: u64,
+    },
+    /// Once when Shortcut Hero starts, without keys.
+    OnStart,
+    /// Once when Shortcut Hero exits on `Ctrl+C` or `SIGTERM`, without keys. The exit waits for
+    /// the actions to finish.
+    OnExit,
+}
+
+impl Serialize for Trigger {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Hold {
+            min_duration_ms: u64,
+        }
+        #[derive(Serialize)]
+        struct DoubleTap {
+            within_ms: u64,
+        }
+
+        // TOML does not support the struct variants, write them as a map with the variant as key
+        // instead, like the other formats do, e.g. `{"hold": {"min_duration_ms": 500}}`
+        match *self {
+            Trigger::Press => serializer.serialize_unit_variant("Trigger", 0, "press"),
+            Trigger::Release => serializer.serialize_unit_variant("Trigger", 1, "release"),
+            Trigger::Hold { min_duration_ms } => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("hold", &Hold { min_duration_ms })?;
+                map.end()
+            }
+            Trigger::DoubleTap { within_ms } => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("double_tap", &DoubleTap { within_ms })?;
+                map.end()
+            }
+            Trigger::OnStart => serializer.serialize_unit_variant("Trigger", 4, "on_start"),
+            Trigger::OnExit => serializer.serialize_unit_variant("Trigger", 5, "on_exit"),
+        }
+    }
+}
+
+impl Trigger {
+    fn is_press(&self) -> bool {
+        *self == Trigger::Press
+    }
+
+    /// Whether the shortcut runs when Shortcut Hero starts or exits instead of on its keys.
+    pub fn is_lifecycle(&self) -> bool {
+        matches!(self, Trigger::OnStart | Trigger::OnExit)
+    }
+}
+
+/// What to do when a shortcut is triggered while it is still running.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Concurrency {
+    /// Run it again at the same time.
+    Parallel,
+    /// Run it again once the current run is finished.
+    #[default]
+    Queue,
+    /// Ignore the trigger.
+    DropIfRunning,
+    /// Cancel the current run and run it again.
+    Restart,
+}
+
+impl Concurrency {
+    fn is_queue(&self) -> bool {
+        *self == Concurrency::Queue
     }
 }
 
@@ -140,14 +424,85 @@ pub enum ShortcutResult {
     EndProgram(String),
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
-pub struct KeyboardKey(#[serde(with = "KeybdKeyDef")] pub KeybdKey);
+/// Physical modifier keys, put last in a key combo.
+pub(crate) const MODIFIER_KEYS: [KeybdKey; 8] = [
+    KeybdKey::LSuper,
+    KeybdKey::RSuper,
+    KeybdKey::LShiftKey,
+    KeybdKey::RShiftKey,
+    KeybdKey::LControlKey,
+    KeybdKey::RControlKey,
+    KeybdKey::LAltKey,
+    KeybdKey::RAltKey,
+];
+
+/// A keyboard key or a mouse button, serialized with its inputbot name (e.g. `LControlKey`) and
+/// deserialized from either its inputbot name or a human-readable name (e.g. `Ctrl`), see
+/// `crate::keys`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum KeyboardKey {
+    /// A physical key, e.g. `LControlKey`.
+    Key(KeybdKey),
+    /// A mouse button, e.g. `X1Button`.
+    Mouse(MouseButton),
+    /// Either the left or the right control key.
+    Ctrl,
+    /// Either the left or the right shift key.
+    Shift,
+    /// Either the left or the right alt key.
+    Alt,
+    /// Either the left or the right super (Windows, Command) key.
+    Super,
+}
+
+impl KeyboardKey {
+    /// Get the physical keys matching this key.
+    pub fn physical_keys(&self) -> Vec<PhysicalKey> {
+        let keys = match self {
+            KeyboardKey::Key(key) => vec![*key],
+            KeyboardKey::Mouse(button) => return vec![PhysicalKey::Mouse(*button)],
+            KeyboardKey::Ctrl => vec![KeybdKey::LControlKey, KeybdKey::RControlKey],
+            KeyboardKey::Shift => vec![KeybdKey::LShiftKey, KeybdKey::RShiftKey],
+            KeyboardKey::Alt => vec![KeybdKey::LAltKey, KeybdKey::RAltKey],
+            KeyboardKey::Super => vec![KeybdKey::LSuper, KeybdKey::RSuper],
+        };
+        keys.into_iter().map(PhysicalKey::Keyboard).collect()
+    }
+
+    /// Whether the physical key `key` is this key.
+    pub fn matches(&self, key: PhysicalKey) -> bool {
+        self.physical_keys().contains(&key)
+    }
+
+    /// Whether this key is currently pressed, any side for the side-agnostic modifiers.
+    pub fn is_pressed(&self, backend: &dyn InputBackend) -> bool {
+        self.physical_keys()
+            .iter()
+            .any(|key| backend.is_pressed(*key))
+    }
+
+    pub fn is_modifier(&self) -> bool {
+        self.physical_keys().iter().all(|key| match key {
+            PhysicalKey::Keyboard(key) => MODIFIER_KEYS.contains(key),
+            PhysicalKey::Mouse(_) => false,
+        })
+    }
+}
 
-#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Serialize, Deserialize)]
+/// A physical keyboard key or mouse button, as sent by the OS.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum PhysicalKey {
+    Keyboard(KeybdKey),
+    Mouse(MouseButton),
+}
+
+/// A keyboard key.
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(remote = "KeybdKey")]
+#[schemars(rename = "KeybdKey")]
 #[derive(TS)]
 #[ts(export)]
-enum KeybdKeyDef {
+pub(crate) enum KeybdKeyDef {
     BackspaceKey,
     TabKey,
     EnterKey,
@@ -271,5 +626,25 @@ enum KeybdKeyDef {
     EqualKey,
 
     #[ts(skip)]
+    #[schemars(skip)]
     OtherKey(u64),
 }
+
+/// A mouse button.
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(remote = "MouseButton")]
+#[schemars(rename = "MouseButton")]
+#[derive(TS)]
+#[ts(export)]
+pub(crate) enum MouseButtonDef {
+    LeftButton,
+    MiddleButton,
+    RightButton,
+    X1Button,
+    X2Button,
+
+    #[ts(skip)]
+    #[schemars(skip)]
+    OtherButton(u32),
+}
@@ -0,0 +1,126 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::hotkey::PhysicalKey;
+
+/// Callback of a bound key, called every time the key is pressed.
+pub type KeyCallback = Arc<dyn Fn() + Send + Sync>;
+
+/// Source of the keyboard and mouse events.
+pub trait InputBackend: Send + Sync {
+    /// Call `callback` every time `key` is pressed, replaces the previous callback of the key.
+    fn bind(&self, key: PhysicalKey, callback: KeyCallback);
+
+    /// Stop calling the callback of `key`.
+    fn unbind(&self, key: PhysicalKey);
+
+    /// Whether `key` is currently pressed.
+    fn is_pressed(&self, key: PhysicalKey) -> bool;
+
+    /// Listen to the events and call the callbacks of the bound keys, blocks forever.
+    fn run(&self);
+}
+
+/// Real keyboard and mouse events, from inputbot.
+pub struct InputbotBackend;
+
+impl InputBackend for InputbotBackend {
+    fn bind(&self, key: PhysicalKey, callback: KeyCallback) {
+        match key {
+            PhysicalKey::Keyboard(key) => key.bind(move || callback()),
+            PhysicalKey::Mouse(button) => button.bind(move || callback()),
+        }
+    }
+
+    fn unbind(&self, key: PhysicalKey) {
+        match key {
+            PhysicalKey::Keyboard(key) => key.unbind(),
+            PhysicalKey::Mouse(button) => button.unbind(),
+        }
+    }
+
+    fn is_pressed(&self, key: PhysicalKey) -> bool {
+        match key {
+            PhysicalKey::Keyboard(key) => key.is_pressed(),
+            PhysicalKey::Mouse(button) => button.is_pressed(),
+        }
+    }
+
+    fn run(&self) {
+        inputbot::handle_input_events();
+    }
+}
+
+/// An event fed to the `SimulatedBackend`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEvent {
+    Press(PhysicalKey),
+    Release(PhysicalKey),
+    /// Wait before the next event, e.g. to hold a key.
+    Wait(Duration),
+}
+
+/// Simulated keyboard and mouse, the events are fed by hand. Used to test the shortcuts without a
+/// real keyboard.
+///
+/// The callbacks are called synchronously, like the OS would, a key that is already pressed
+/// calls its callback again (key repeat).
+#[derive(Default)]
+pub struct SimulatedBackend {
+    pressed: Mutex<HashSet<PhysicalKey>>,
+    callbacks: Mutex<HashMap<PhysicalKey, KeyCallback>>,
+}
+
+impl SimulatedBackend {
+    pub fn new() -> Arc<SimulatedBackend> {
+        Arc::new(SimulatedBackend::default())
+    }
+
+    /// Press a key and call its callback, if it is bound.
+    pub fn press(&self, key: PhysicalKey) {
+        self.pressed.lock().unwrap().insert(key);
+        let callback = self.callbacks.lock().unwrap().get(&key).cloned();
+        if let Some(callback) = callback {
+            callback();
+        }
+    }
+
+    pub fn release(&self, key: PhysicalKey) {
+        self.pressed.lock().unwrap().remove(&key);
+    }
+
+    /// Feed the events in order.
+    pub fn play(&self, events: &[InputEvent]) {
+        for event in events {
+            match event {
+                InputEvent::Press(key) => self.press(*key),
+                InputEvent::Release(key) => self.release(*key),
+                InputEvent::Wait(duration) => thread::sleep(*duration),
+            }
+        }
+    }
+
+    /// Get the keys that are currently bound.
+    pub fn bound_keys(&self) -> HashSet<PhysicalKey> {
+        self.callbacks.lock().unwrap().keys().copied().collect()
+    }
+}
+
+impl InputBackend for SimulatedBackend {
+    fn bind(&self, key: PhysicalKey, callback: KeyCallback) {
+        self.callbacks.lock().unwrap().insert(key, callback);
+    }
+
+    fn unbind(&self, key: PhysicalKey) {
+        self.callbacks.lock().unwrap().remove(&key);
+    }
+
+    fn is_pressed(&self, key: PhysicalKey) -> bool {
+        self.pressed.lock().unwrap().contains(&key)
+    }
+
+    /// Nothing to listen to, the events are fed with `press`, `release` and `play`.
+    fn run(&self) {}
+}
@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, DirBuilder};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::executor::Executor;
+use crate::Config;
+
+const SOCKET_FILE_NAME: &str = "shortcut-hero.sock";
+
+/// Control socket served by this process, removed when exiting.
+static SERVED_SOCKET: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// A request sent to the control socket, one JSON object per line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+    /// Run a shortcut and wait for its result.
+    Trigger {
+        /// Id of the shortcut, its index in `keyboard_shortcuts` (starts at 0) or its description.
+        id: String,
+        /// Initial `{{input}}` of the shortcut.
+        #[serde(default)]
+        input: String,
+        /// Variables overriding the initial variables of the shortcut.
+        #[serde(default)]
+        variables: HashMap<String, String>,
+    },
+    /// List the shortcuts of every mode.
+    List,
+}
+
+/// The response to a request, one JSON object per line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Response {
+    /// The shortcut ran to the end.
+    Triggered {
+        /// Output of the last action.
+        output: String,
+        /// Variables at the end of the run.
+        variables: HashMap<String, String>,
+    },
+    Shortcuts {
+        shortcuts: Vec<ShortcutInfo>,
+    },
+    /// The request is invalid or the shortcut failed.
+    Error {
+        message: String,
+    },
+}
+
+/// A shortcut listed by the `list` command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShortcutInfo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub mode: String,
+    pub keys: String,
+    pub description: String,
+}
+
+/// Get the path of the control socket of the current user, in `$XDG_RUNTIME_DIR` if it exists,
+/// else in a `shortcut-hero-<uid>` directory of the temporary directory.
+pub fn default_socket_path() -> PathBuf {
+    match dirs::runtime_dir() {
+        Some(dir) => dir.join(SOCKET_FILE_NAME),
+        None => env::temp_dir()
+            .join(format!("shortcut-hero-{}", current_uid()))
+            .join(SOCKET_FILE_NAME),
+    }
+}
+
+fn current_uid() -> u32 {
+    // SAFETY: `getuid` is always successful and has no side effects
+    unsafe { libc::getuid() }
+}
+
+/// Fail if the directory of the socket could be written by another user, who could replace the
+/// socket with their own.
+fn check_private_dir(path: &Path) -> anyhow::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("/"));
+    let metadata = fs::symlink_metadata(dir)
+        .with_context(|| format!("Could not read the control socket directory {dir:?}"))?;
+    if !metadata.is_dir() || metadata.uid() != current_uid() || metadata.mode() & 0o077 != 0 {
+        return Err(anyhow!(
+            "Control socket directory {dir:?} must be a directory only accessible by the current \
+             user"
+        ));
+    }
+    Ok(())
+}
+
+/// Listen on the control socket at `path`, on a thread of its own, so other programs can trigger
+/// the shortcuts of the current configuration.
+///
+/// The shortcuts are run by the executor, like when their keys are pressed. The socket is only
+/// accessible by the current user.
+pub fn serve(
+    path: &Path,
+    config: Arc<Mutex<Arc<Config>>>,
+    executor: Arc<Executor>,
+) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .with_context(|| format!("Could not create the control socket directory {dir:?}"))?;
+    }
+    check_private_dir(path)?;
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(anyhow!(
+                "Control socket {path:?} is already used, is Shortcut Hero already running?"
+            ));
+        }
+        // Left behind by a Shortcut Hero that did not exit gracefully
+        fs::remove_file(path)
+            .with_context(|| format!("Could not remove the stale control socket {path:?}"))?;
+    }
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("Could not listen on the control socket {path:?}"))?;
+    // The directory already keeps the other users out, the umask is not changed as it is shared
+    // by the whole process
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+        .with_context(|| format!("Could not restrict the control socket {path:?}"))?;
+    *SERVED_SOCKET.lock().unwrap() = Some(path.to_path_buf());
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let config = config.clone();
+            let executor = executor.clone();
+            thread::spawn(move || {
+                if let Err(e) = handle_connection(stream, &config, &executor) {
+                    eprintln!("Control socket connection failed: {e:#}");
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+/// Remove the control socket served by this process, if any.
+pub fn remove_socket() {
+    if let Some(path) = SERVED_SOCKET.lock().unwrap().take() {
+        fs::remove_file(path).ok();
+    }
+}
+
+/// Answer the requests of a connection until it is closed.
+fn handle_connection(
+    stream: UnixStream,
+    config: &Mutex<Arc<Config>>,
+    executor: &Executor,
+) -> anyhow::Result<()> {
+    let reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                let config = config.lock().unwrap().clone();
+                handle_request(request, &config, executor)
+            }
+            Err(e) => Response::Error {
+                message: format!("Invalid request: {e}"),
+            },
+        };
+        write_line(&mut writer, &response)?;
+    }
+    Ok(())
+}
+
+fn handle_request(request: Request, config: &Arc<Config>, executor: &Executor) -> Response {
+    match request {
+        Request::Trigger {
+            id,
+            input,
+            variables,
+        } => {
+            let Some(shortcut) = config.find_shortcut(&id) else {
+                return Response::Error {
+                    message: format!("No shortcut found matching `{id}`"),
+                };
+            };
+            match executor.trigger(shortcut, config, &input, variables) {
+                Ok(result) => Response::Triggered {
+                    output: result.output,
+                    variables: result.variables,
+                },
+                Err(e) => Response::Error {
+                    message: format!("{e:#}"),
+                },
+            }
+        }
+        Request::List => {
+            let mut shortcuts = Vec::new();
+            for (mode, mode_shortcuts) in config.shortcuts_by_mode() {
+                for shortcut in mode_shortcuts {
+                    shortcuts.push(ShortcutInfo {
+                        id: shortcut.id.clone(),
+                        mode: mode.to_string(),
+                        keys: shortcut.format_keys(),
+                        description: shortcut.description.clone(),
+                    });
+                }
+            }
+            Response::Shortcuts { shortcuts }
+        }
+    }
+}
+
+/// Send a request to the Shortcut Hero listening on the control socket at `path`, and wait for
+/// its response.
+pub fn send(path: &Path, request: &Request) -> anyhow::Result<Response> {
+    check_private_dir(path)?;
+    let mut stream = UnixStream::connect(path).with_context(|| {
+        format!("Could not connect to the control socket {path:?}, is Shortcut Hero running?")
+    })?;
+    write_line(&mut stream, request)?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    serde_json::from_str(&line).context("Invalid response from the control socket")
+}
+
+/// Write a JSON message and its line break at once, for the clients reading it in one go.
+fn write_line<T: Serialize>(stream: &mut UnixStream, message: &T) -> anyhow::Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    Ok(())
+}
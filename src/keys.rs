@@ -0,0 +1,530 @@
+use std::fmt;
+
+use anyhow::anyhow;
+use inputbot::KeybdKey;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{
+    ArrayValidation, InstanceType, Metadata, Schema, SchemaObject, SubschemaValidation,
+};
+use schemars::JsonSchema;
+use serde::de::value::StrDeserializer;
+use serde::de::{self, IntoDeserializer, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::hotkey::{KeybdKeyDef, KeyboardKey, MouseButton, MouseButtonDef};
+
+/// Human-readable names of the keys, case-insensitive. The first name is the one used to print
+/// the key.
+///
+/// Letters, digits, `F1` to `F24` and `Numpad0` to `Numpad9` are handled separately.
+const KEY_NAMES: &[(KeybdKey, &[&str])] = &[
+    (KeybdKey::LControlKey, &["LCtrl"]),
+    (KeybdKey::RControlKey, &["RCtrl"]),
+    (KeybdKey::LShiftKey, &["LShift"]),
+    (KeybdKey::RShiftKey, &["RShift"]),
+    (KeybdKey::LAltKey, &["LAlt"]),
+    (KeybdKey::RAltKey, &["RAlt", "AltGr"]),
+    (KeybdKey::LSuper, &["LSuper"]),
+    (KeybdKey::RSuper, &["RSuper"]),
+    (KeybdKey::BackspaceKey, &["Backspace"]),
+    (KeybdKey::TabKey, &["Tab"]),
+    (KeybdKey::EnterKey, &["Enter", "Return"]),
+    (KeybdKey::EscapeKey, &["Esc", "Escape"]),
+    (KeybdKey::SpaceKey, &["Space"]),
+    (KeybdKey::PageUpKey, &["PageUp", "PgUp"]),
+    (KeybdKey::PageDownKey, &["PageDown", "PgDn"]),
+    (KeybdKey::EndKey, &["End"]),
+    (KeybdKey::HomeKey, &["Home"]),
+    (KeybdKey::LeftKey, &["Left"]),
+    (KeybdKey::UpKey, &["Up"]),
+    (KeybdKey::RightKey, &["Right"]),
+    (KeybdKey::DownKey, &["Down"]),
+    (KeybdKey::InsertKey, &["Insert", "Ins"]),
+    (KeybdKey::DeleteKey, &["Delete", "Del"]),
+    (KeybdKey::NumLockKey, &["NumLock"]),
+    (KeybdKey::ScrollLockKey, &["ScrollLock"]),
+    (KeybdKey::CapsLockKey, &["CapsLock"]),
+    (KeybdKey::BrowserBackKey, &["BrowserBack"]),
+    (KeybdKey::BrowserForwardKey, &["BrowserForward"]),
+    (KeybdKey::BrowserRefreshKey, &["BrowserRefresh"]),
+    (KeybdKey::VolumeMuteKey, &["VolumeMute"]),
+    (KeybdKey::VolumeDownKey, &["VolumeDown"]),
+    (KeybdKey::VolumeUpKey, &["VolumeUp"]),
+    (KeybdKey::MediaNextTrackKey, &["MediaNext"]),
+    (KeybdKey::MediaPrevTrackKey, &["MediaPrev"]),
+    (KeybdKey::MediaStopKey, &["MediaStop"]),
+    (KeybdKey::MediaPlayPauseKey, &["MediaPlayPause"]),
+    (KeybdKey::BackquoteKey, &["`", "Backquote"]),
+    (KeybdKey::SlashKey, &["/", "Slash"]),
+    (KeybdKey::BackslashKey, &["\\", "Backslash"]),
+    (KeybdKey::CommaKey, &[",", "Comma"]),
+    (KeybdKey::PeriodKey, &[".", "Period"]),
+    (KeybdKey::MinusKey, &["-", "Minus"]),
+    (KeybdKey::QuoteKey, &["'", "Quote"]),
+    (KeybdKey::SemicolonKey, &[";", "Semicolon"]),
+    (KeybdKey::LBracketKey, &["[", "LBracket"]),
+    (KeybdKey::RBracketKey, &["]", "RBracket"]),
+    (KeybdKey::EqualKey, &["=", "Equal"]),
+];
+
+/// Names of the side-agnostic modifiers, case-insensitive. The first name is the one used to
+/// print and serialize the key.
+const MODIFIER_NAMES: [(KeyboardKey, &[&str]); 4] = [
+    (KeyboardKey::Ctrl, &["Ctrl", "Control"]),
+    (KeyboardKey::Shift, &["Shift"]),
+    (KeyboardKey::Alt, &["Alt"]),
+    (KeyboardKey::Super, &["Super", "Win", "Meta", "Cmd"]),
+];
+
+/// Names of the mouse buttons, case-insensitive. The first name is the one used to print the
+/// button.
+const MOUSE_BUTTON_NAMES: [(MouseButton, &[&str]); 5] = [
+    (MouseButton::LeftButton, &["MouseLeft", "Mouse1"]),
+    (MouseButton::RightButton, &["MouseRight", "Mouse2"]),
+    (MouseButton::MiddleButton, &["MouseMiddle", "Mouse3"]),
+    (MouseButton::X1Button, &["Mouse4", "MouseBack"]),
+    (MouseButton::X2Button, &["Mouse5", "MouseForward"]),
+];
+
+const LETTER_KEYS: [KeybdKey; 26] = [
+    KeybdKey::AKey,
+    KeybdKey::BKey,
+    KeybdKey::CKey,
+    KeybdKey::DKey,
+    KeybdKey::EKey,
+    KeybdKey::FKey,
+    KeybdKey::GKey,
+    KeybdKey::HKey,
+    KeybdKey::IKey,
+    KeybdKey::JKey,
+    KeybdKey::KKey,
+    KeybdKey::LKey,
+    KeybdKey::MKey,
+    KeybdKey::NKey,
+    KeybdKey::OKey,
+    KeybdKey::PKey,
+    KeybdKey::QKey,
+    KeybdKey::RKey,
+    KeybdKey::SKey,
+    KeybdKey::TKey,
+    KeybdKey::UKey,
+    KeybdKey::VKey,
+    KeybdKey::WKey,
+    KeybdKey::XKey,
+    KeybdKey::YKey,
+    KeybdKey::ZKey,
+];
+
+const NUMROW_KEYS: [KeybdKey; 10] = [
+    KeybdKey::Numrow0Key,
+    KeybdKey::Numrow1Key,
+    KeybdKey::Numrow2Key,
+    KeybdKey::Numrow3Key,
+    KeybdKey::Numrow4Key,
+    KeybdKey::Numrow5Key,
+    KeybdKey::Numrow6Key,
+    KeybdKey::Numrow7Key,
+    KeybdKey::Numrow8Key,
+    KeybdKey::Numrow9Key,
+];
+
+const NUMPAD_KEYS: [KeybdKey; 10] = [
+    KeybdKey::Numpad0Key,
+    KeybdKey::Numpad1Key,
+    KeybdKey::Numpad2Key,
+    KeybdKey::Numpad3Key,
+    KeybdKey::Numpad4Key,
+    KeybdKey::Numpad5Key,
+    KeybdKey::Numpad6Key,
+    KeybdKey::Numpad7Key,
+    KeybdKey::Numpad8Key,
+    KeybdKey::Numpad9Key,
+];
+
+const FUNCTION_KEYS: [KeybdKey; 24] = [
+    KeybdKey::F1Key,
+    KeybdKey::F2Key,
+    KeybdKey::F3Key,
+    KeybdKey::F4Key,
+    KeybdKey::F5Key,
+    KeybdKey::F6Key,
+    KeybdKey::F7Key,
+    KeybdKey::F8Key,
+    KeybdKey::F9Key,
+    KeybdKey::F10Key,
+    KeybdKey::F11Key,
+    KeybdKey::F12Key,
+    KeybdKey::F13Key,
+    KeybdKey::F14Key,
+    KeybdKey::F15Key,
+    KeybdKey::F16Key,
+    KeybdKey::F17Key,
+    KeybdKey::F18Key,
+    KeybdKey::F19Key,
+    KeybdKey::F20Key,
+    KeybdKey::F21Key,
+    KeybdKey::F22Key,
+    KeybdKey::F23Key,
+    KeybdKey::F24Key,
+];
+
+/// Parse a key name, e.g. `Ctrl`, `B`, `F12`, `Mouse4` or the inputbot name `LControlKey`.
+pub fn parse_key(name: &str) -> anyhow::Result<KeyboardKey> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(anyhow!("Key name is empty"));
+    }
+
+    let deserializer: StrDeserializer<de::value::Error> = name.into_deserializer();
+    if let Ok(key) = KeybdKeyDef::deserialize(deserializer) {
+        return Ok(KeyboardKey::Key(key));
+    }
+    let deserializer: StrDeserializer<de::value::Error> = name.into_deserializer();
+    if let Ok(button) = MouseButtonDef::deserialize(deserializer) {
+        return Ok(KeyboardKey::Mouse(button));
+    }
+
+    let lowercase = name.to_lowercase();
+    let modifier = MODIFIER_NAMES.iter().find(|(_, names)| {
+        names
+            .iter()
+            .any(|modifier_name| modifier_name.to_lowercase() == lowercase)
+    });
+    if let Some((modifier, _)) = modifier {
+        return Ok(*modifier);
+    }
+
+    let mouse_button = MOUSE_BUTTON_NAMES.iter().find(|(_, names)| {
+        names
+            .iter()
+            .any(|button_name| button_name.to_lowercase() == lowercase)
+    });
+    if let Some((button, _)) = mouse_button {
+        return Ok(KeyboardKey::Mouse(*button));
+    }
+
+    let named_key = KEY_NAMES.iter().find(|(_, names)| {
+        names
+            .iter()
+            .any(|key_name| key_name.to_lowercase() == lowercase)
+    });
+    if let Some((key, _)) = named_key {
+        return Ok(KeyboardKey::Key(*key));
+    }
+
+    let mut chars = lowercase.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_lowercase() {
+            return Ok(KeyboardKey::Key(LETTER_KEYS[(c as u8 - b'a') as usize]));
+        }
+        if c.is_ascii_digit() {
+            return Ok(KeyboardKey::Key(NUMROW_KEYS[(c as u8 - b'0') as usize]));
+        }
+    }
+
+    let numbered_key = |prefix: &str, keys: &[KeybdKey], first: usize| {
+        lowercase
+            .strip_prefix(prefix)
+            .and_then(|number| number.parse::<usize>().ok())
+            .and_then(|number| number.checked_sub(first))
+            .and_then(|index| keys.get(index).copied())
+    };
+    if let Some(key) = numbered_key("f", &FUNCTION_KEYS, 1) {
+        return Ok(KeyboardKey::Key(key));
+    }
+    if let Some(key) = numbered_key("numpad", &NUMPAD_KEYS, 0) {
+        return Ok(KeyboardKey::Key(key));
+    }
+    if let Some(code) = lowercase
+        .strip_prefix("mousebutton")
+        .and_then(|code| code.parse::<u32>().ok())
+    {
+        return Ok(KeyboardKey::Mouse(MouseButton::OtherButton(code)));
+    }
+    if let Some(code) = lowercase
+        .strip_prefix("key")
+        .and_then(|code| code.parse::<u64>().ok())
+    {
+        return Ok(KeyboardKey::Key(KeybdKey::OtherKey(code)));
+    }
+
+    if lowercase.contains("wheel") || lowercase.starts_with("scroll") {
+        return Err(anyhow!(
+            "The mouse wheel can not be used in keys, inputbot only reports the presses of the \
+             mouse buttons"
+        ));
+    }
+
+    Err(anyhow!(
+        "Unknown key `{name}`, use names like `Ctrl`, `Shift`, `Alt`, `Super`, `A`, `1`, `F12`, \
+         `Enter` or `Space`"
+    ))
+}
+
+/// Parse a key combo, keys to press at the same time separated by `+`, e.g. `Ctrl+Shift+B`.
+pub fn parse_key_combo(combo: &str) -> anyhow::Result<Vec<KeyboardKey>> {
+    if combo.trim().is_empty() {
+        return Err(anyhow!("Key combo is empty"));
+    }
+    combo
+        .split('+')
+        .map(|name| parse_key(name).map_err(|e| anyhow!("{e} in key combo `{combo}`")))
+        .collect()
+}
+
+/// Parse a sequence of key combos to press one after the other, separated by spaces, e.g.
+/// `Ctrl+K Ctrl+C`.
+pub fn parse_key_sequence(sequence: &str) -> anyhow::Result<Vec<Vec<KeyboardKey>>> {
+    if sequence.trim().is_empty() {
+        return Err(anyhow!("Key sequence is empty"));
+    }
+    sequence.split_whitespace().map(parse_key_combo).collect()
+}
+
+/// Get the human-readable name of a key, parsed back by `parse_key`.
+pub fn key_name(key: &KeyboardKey) -> String {
+    let key = match key {
+        KeyboardKey::Key(key) => *key,
+        KeyboardKey::Mouse(button) => {
+            return match MOUSE_BUTTON_NAMES.iter().find(|(b, _)| b == button) {
+                Some((_, names)) => names[0].to_string(),
+                None => match button {
+                    MouseButton::OtherButton(code) => format!("MouseButton{code}"),
+                    _ => format!("{button:?}"),
+                },
+            };
+        }
+        modifier => {
+            let (_, names) = MODIFIER_NAMES
+                .iter()
+                .find(|(named_modifier, _)| named_modifier == modifier)
+                .expect("every modifier has a name");
+            return names[0].to_string();
+        }
+    };
+    if let Some((_, names)) = KEY_NAMES.iter().find(|(named_key, _)| *named_key == key) {
+        return names[0].to_string();
+    }
+    if let Some(i) = LETTER_KEYS.iter().position(|k| *k == key) {
+        return ((b'A' + i as u8) as char).to_string();
+    }
+    if let Some(i) = NUMROW_KEYS.iter().position(|k| *k == key) {
+        return i.to_string();
+    }
+    if let Some(i) = NUMPAD_KEYS.iter().position(|k| *k == key) {
+        return format!("Numpad{i}");
+    }
+    if let Some(i) = FUNCTION_KEYS.iter().position(|k| *k == key) {
+        return format!("F{}", i + 1);
+    }
+    match key {
+        KeybdKey::OtherKey(code) => format!("Key{code}"),
+        _ => format!("{key:?}"),
+    }
+}
+
+/// Format a key combo, e.g. `Ctrl+Shift+B`.
+pub fn format_key_combo(keys: &[KeyboardKey]) -> String {
+    keys.iter().map(key_name).collect::<Vec<String>>().join("+")
+}
+
+/// Format a sequence of key combos, e.g. `Ctrl+K Ctrl+C`.
+pub fn format_key_sequence(sequence: &[&[KeyboardKey]]) -> String {
+    sequence
+        .iter()
+        .map(|combo| format_key_combo(combo))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+impl fmt::Display for KeyboardKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", key_name(self))
+    }
+}
+
+impl Serialize for KeyboardKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            // Not every format supports the `{"OtherKey": 42}` form, e.g. YAML
+            KeyboardKey::Key(KeybdKey::OtherKey(_)) => serializer.serialize_str(&key_name(self)),
+            KeyboardKey::Key(key) => KeybdKeyDef::serialize(key, serializer),
+            KeyboardKey::Mouse(MouseButton::OtherButton(_)) => {
+                serializer.serialize_str(&key_name(self))
+            }
+            KeyboardKey::Mouse(button) => MouseButtonDef::serialize(button, serializer),
+            modifier => serializer.serialize_str(&key_name(modifier)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyboardKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeyboardKeyVisitor;
+
+        impl<'de> Visitor<'de> for KeyboardKeyVisitor {
+            type Value = KeyboardKey;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a key name like `Ctrl`, `B` or `LControlKey`")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<KeyboardKey, E> {
+                parse_key(value).map_err(E::custom)
+            }
+
+            fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<KeyboardKey, A::Error> {
+                KeybdKeyDef::deserialize(de::value::MapAccessDeserializer::new(map))
+                    .map(KeyboardKey::Key)
+            }
+        }
+
+        deserializer.deserialize_any(KeyboardKeyVisitor)
+    }
+}
+
+/// A key combo, either a string like `Ctrl+Shift+B` or a list of keys.
+struct KeyCombo(Vec<KeyboardKey>);
+
+impl<'de> Deserialize<'de> for KeyCombo {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeyComboVisitor;
+
+        impl<'de> Visitor<'de> for KeyComboVisitor {
+            type Value = KeyCombo;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a key combo like `Ctrl+Shift+B` or a list of keys")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<KeyCombo, E> {
+                parse_key_combo(value).map(KeyCombo).map_err(E::custom)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<KeyCombo, A::Error> {
+                let mut keys = Vec::new();
+                while let Some(key) = seq.next_element::<KeyboardKey>()? {
+                    keys.push(key);
+                }
+                Ok(KeyCombo(keys))
+            }
+        }
+
+        deserializer.deserialize_any(KeyComboVisitor)
+    }
+}
+
+/// Deserialize `keys`, either a string like `Ctrl+Shift+B` or a list of keys.
+pub(crate) fn deserialize_key_combo<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<KeyboardKey>, D::Error> {
+    KeyCombo::deserialize(deserializer).map(|combo| combo.0)
+}
+
+/// Deserialize `sequence`, either a string like `Ctrl+K Ctrl+C` or a list of key combos.
+pub(crate) fn deserialize_key_sequence<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Vec<KeyboardKey>>, D::Error> {
+    struct KeySequenceVisitor;
+
+    impl<'de> Visitor<'de> for KeySequenceVisitor {
+        type Value = Vec<Vec<KeyboardKey>>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a key sequence like `Ctrl+K Ctrl+C` or a list of key combos")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+            parse_key_sequence(value).map_err(E::custom)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut sequence = Vec::new();
+            while let Some(combo) = seq.next_element::<KeyCombo>()? {
+                sequence.push(combo.0);
+            }
+            Ok(sequence)
+        }
+    }
+
+    deserializer.deserialize_any(KeySequenceVisitor)
+}
+
+impl JsonSchema for KeyboardKey {
+    fn schema_name() -> String {
+        "KeyboardKey".to_string()
+    }
+
+    /// An inputbot key or mouse button name, a side-agnostic modifier or a human-readable key
+    /// name.
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        let modifiers = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(
+                MODIFIER_NAMES
+                    .iter()
+                    .map(|(_, names)| names[0].into())
+                    .collect(),
+            ),
+            metadata: Some(Box::new(Metadata {
+                description: Some("Left or right modifier key.".to_string()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        any_of(vec![
+            gen.subschema_for::<KeybdKeyDef>(),
+            gen.subschema_for::<MouseButtonDef>(),
+            modifiers.into(),
+            string_schema("Key name like `LCtrl`, `B`, `F12` or `Mouse4`."),
+        ])
+    }
+}
+
+/// JSON Schema of `keys`: a string like `Ctrl+Shift+B` or a list of keys.
+pub(crate) fn key_combo_schema(gen: &mut SchemaGenerator) -> Schema {
+    any_of(vec![
+        string_schema("Keys separated by `+`, e.g. `Ctrl+Shift+B`."),
+        gen.subschema_for::<Vec<KeyboardKey>>(),
+    ])
+}
+
+/// JSON Schema of `sequence`: a string like `Ctrl+K Ctrl+C` or a list of key combos.
+pub(crate) fn key_sequence_schema(gen: &mut SchemaGenerator) -> Schema {
+    let combo = key_combo_schema(gen);
+    let combos = SchemaObject {
+        instance_type: Some(InstanceType::Array.into()),
+        array: Some(Box::new(ArrayValidation {
+            items: Some(combo.into()),
+            ..Default::default()
+        })),
+        ..Default::default()
+    };
+    any_of(vec![
+        string_schema("Key combos separated by spaces, e.g. `Ctrl+K Ctrl+C`."),
+        combos.into(),
+    ])
+}
+
+fn string_schema(description: &str) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        metadata: Some(Box::new(Metadata {
+            description: Some(description.to_string()),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+fn any_of(schemas: Vec<Schema>) -> Schema {
+    SchemaObject {
+        subschemas: Some(Box::new(SubschemaValidation {
+            any_of: Some(schemas),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
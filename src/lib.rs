@@ -1,18 +1,40 @@
-use std::collections::HashSet;
+use std::collections::HashMap;
 use std::ops::Deref;
-use std::sync::Arc;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 
-use inputbot::KeybdKey;
-
-pub use crate::config::Config;
-use crate::hotkey::{KeyboardKey, Shortcut};
+pub use crate::config::{resolve_config_path, Config};
+use crate::dispatch::Dispatcher;
+use crate::executor::{CancelToken, Cancelled, Executor};
+use crate::hotkey::{
+    KeyboardKey, PhysicalKey, Shortcut, TriggerContext, TriggerResult, MODIFIER_KEYS,
+};
+use crate::input::{InputBackend, InputbotBackend};
+use crate::schedule::Scheduler;
+use crate::watch::FileWatcher;
 
 pub mod actions;
 pub mod config;
+pub mod dispatch;
 pub mod evaluation;
+pub mod executor;
 pub mod hotkey;
+pub mod input;
+#[cfg(unix)]
+pub mod ipc;
+pub mod keys;
+pub mod lifecycle;
+pub mod migration;
+pub mod mode;
+pub mod reload;
+pub mod schedule;
+pub mod secret;
+pub mod validation;
+pub mod watch;
+
+pub fn run(config_path: &Path, config: &Config) -> anyhow::Result<()> {
+    println!("Config: {config:#?}\n\n");
 
-pub fn run(config: &Config) {
     // // Uncomment to get the JSON corresponding to some shortcut
     // let shortcut = Shortcut::new(
     //     vec![
@@ -36,55 +58,115 @@ pub fn run(config: &Config) {
     //     serde_json::to_string_pretty(&shortcut).unwrap()
     // );
 
-    register_hotkeys(config);
+    let backend: Arc<dyn InputBackend> = Arc::new(InputbotBackend);
+    let executor = Executor::new(config.max_concurrent_runs(), Arc::new(run_shortcut));
+    let current_config = Arc::new(Mutex::new(Arc::new(config.clone())));
+    let config = current_config.lock().unwrap().clone();
+
+    lifecycle::exit_on_signal(current_config.clone(), executor.clone())?;
+    let bound_keys = Arc::new(Mutex::new(register_hotkeys(&backend, &executor, &config)));
+    let scheduler = Mutex::new(Scheduler::start(config.clone(), executor.clone()));
+    let file_watcher = Mutex::new(FileWatcher::start(config.clone(), executor.clone())?);
+    #[cfg(unix)]
+    if let Err(e) = ipc::serve(
+        &ipc::default_socket_path(),
+        current_config.clone(),
+        executor.clone(),
+    ) {
+        eprintln!("{e:#}, `shortcut-hero trigger` will not reach this instance");
+    }
+    lifecycle::run_start_shortcuts(&config, executor.as_ref());
+
+    // Rebind every shortcut and restart the scheduler and the file watches when the configuration
+    // file changes, the reloaded configuration is already validated here
+    let reload_backend = backend.clone();
+    reload::watch_config(config_path, &config, move |config| {
+        let config = Arc::new(config);
+        if !config.has_mode(&mode::current_mode()) {
+            println!("Mode `{}` does not exist anymore", mode::current_mode());
+            mode::set_current_mode(mode::DEFAULT_MODE);
+        }
+
+        let mut bound_keys = bound_keys.lock().unwrap();
+        bound_keys
+            .drain(..)
+            .for_each(|key| reload_backend.unbind(key));
+        *bound_keys = register_hotkeys(&reload_backend, &executor, &config);
+
+        executor.set_max_concurrent_runs(config.max_concurrent_runs());
+
+        let mut scheduler = scheduler.lock().unwrap();
+        scheduler.stop();
+        *scheduler = Scheduler::start(config.clone(), executor.clone());
+
+        match FileWatcher::start(config.clone(), executor.clone()) {
+            Ok(watcher) => *file_watcher.lock().unwrap() = watcher,
+            Err(e) => eprintln!("Could not watch the files of the shortcuts: {e:#}"),
+        }
+
+        *current_config.lock().unwrap() = config.clone();
+        println!(
+            "Config reloaded, {} shortcuts registered",
+            config.all_shortcuts().count()
+        );
+    })?;
+
+    backend.run();
+    Ok(())
+}
+
+/// Bind the keys of the shortcuts of every mode, returns the keys that were bound.
+///
+/// Each key is bound once, the shortcuts to run are picked from the current mode when the key is
+/// pressed, and run by the executor.
+fn register_hotkeys(
+    backend: &Arc<dyn InputBackend>,
+    executor: &Arc<Executor>,
+    config: &Arc<Config>,
+) -> Vec<PhysicalKey> {
+    Dispatcher::new(config.clone(), backend.clone(), executor.clone()).bind()
 }
 
-fn register_hotkeys(config: &Config) {
-    let config = Arc::new(config.clone());
-
-    config
-        .clone()
-        .keyboard_shortcuts
-        .clone()
-        .into_iter()
-        .for_each(|shortcut| match shortcut.clone().keys.len() {
-            0 => println!("Shortcut does not have keyboard keys defined - {shortcut:#?}"),
-            _ => {
-                let config = config.clone();
-                let mut keys = shortcut.clone().keys;
-                sort_keys_with_modifier_last(&mut keys);
-                let keys = keys.into_iter();
-
-                let first = keys.clone().nth(0).unwrap();
-                let rest = keys.clone().skip(1);
-                first.0.bind(move || {
-                    let all_pressed = rest.clone().all(|x| x.0.is_pressed());
-                    if all_pressed {
-                        run_shortcut(&shortcut, &config)
-                    }
-                });
-            }
-        });
-
-    inputbot::handle_input_events();
+/// Whether `pressed_key` completes the key combo, with every other key of the combo pressed
+///
+/// With `exact_match`, no other modifier than the ones of the combo must be pressed.
+pub(crate) fn is_combo_pressed(
+    backend: &dyn InputBackend,
+    keys: &[KeyboardKey],
+    pressed_key: PhysicalKey,
+    exact_match: bool,
+) -> bool {
+    let keys = get_sorted_keys(keys);
+    let is_pressed = match keys.split_first() {
+        Some((first, rest)) => {
+            first.matches(pressed_key) && rest.iter().all(|x| x.is_pressed(backend))
+        }
+        None => false,
+    };
+    is_pressed && !(exact_match && has_other_modifier_pressed(backend, &keys))
+}
+
+/// Whether a modifier that is not part of the key combo is pressed
+fn has_other_modifier_pressed(backend: &dyn InputBackend, keys: &[KeyboardKey]) -> bool {
+    MODIFIER_KEYS
+        .iter()
+        .map(|modifier| PhysicalKey::Keyboard(*modifier))
+        .any(|modifier| {
+            backend.is_pressed(modifier) && !keys.iter().any(|key| key.matches(modifier))
+        })
+}
+
+pub(crate) fn get_sorted_keys(keys: &[KeyboardKey]) -> Vec<KeyboardKey> {
+    let mut keys = keys.to_vec();
+    sort_keys_with_modifier_last(&mut keys);
+    keys
 }
 
 /// Put the modifiers at the end of the vector, else the keys combo might not work
 fn sort_keys_with_modifier_last(keys: &mut Vec<KeyboardKey>) {
-    let modifiers = HashSet::from([
-        KeybdKey::LSuper,
-        KeybdKey::RSuper,
-        KeybdKey::LShiftKey,
-        KeybdKey::RShiftKey,
-        KeybdKey::LControlKey,
-        KeybdKey::RControlKey,
-        KeybdKey::LAltKey,
-        KeybdKey::RAltKey,
-    ]);
-
     keys.sort_by(|a, b| {
-        let a_is_modifier = modifiers.contains(&a.0);
-        let b_is_modifier = modifiers.contains(&b.0);
+        let a_is_modifier = a.is_modifier();
+        let b_is_modifier = b.is_modifier();
 
         match (a_is_modifier, b_is_modifier) {
             (true, true) => std::cmp::Ordering::Equal,
@@ -95,15 +177,29 @@ fn sort_keys_with_modifier_last(keys: &mut Vec<KeyboardKey>) {
     });
 }
 
-fn run_shortcut(shortcut: &Shortcut, config: &Arc<Config>) {
+pub(crate) fn run_shortcut(
+    shortcut: &Shortcut,
+    config: &Arc<Config>,
+    input: &str,
+    args: HashMap<String, String>,
+    cancel: &CancelToken,
+) -> anyhow::Result<TriggerResult> {
     println!("\nRunning {:?}", shortcut);
-    let result = shortcut.trigger(config.deref());
-    match result {
+    let context = TriggerContext {
+        depth: 0,
+        cancel: cancel.clone(),
+    };
+    let result = shortcut.trigger_with(config.deref(), &context, input, args);
+    match &result {
         Ok(_result_str) => {
             // println!("Result [{}] for {:?}", result_str, shortcut)
         }
+        Err(e) if e.is::<Cancelled>() => {
+            println!("Cancelled {:?}", shortcut)
+        }
         Err(e) => {
             eprintln!("Failed [{}] for {:?}", e, shortcut)
         }
     }
+    result
 }
@@ -0,0 +1,95 @@
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Context;
+
+use crate::dispatch::ShortcutRunner;
+use crate::executor::Executor;
+use crate::hotkey::{Shortcut, Trigger};
+use crate::Config;
+
+/// Run the `on_start` shortcuts of every mode with `runner`.
+pub fn run_start_shortcuts(config: &Arc<Config>, runner: &dyn ShortcutRunner) {
+    lifecycle_shortcuts(config, Trigger::OnStart).for_each(|shortcut| runner.run(shortcut, config));
+}
+
+/// Longest wait for the `on_exit` shortcuts to finish before exiting anyway.
+const ON_EXIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Exit gracefully on `SIGINT` and `SIGTERM` (`Ctrl+C` on Windows), after running the `on_exit`
+/// shortcuts of every mode of the current configuration with the executor.
+///
+/// The exit waits for the shortcuts still running, at most `ON_EXIT_TIMEOUT`. A second signal
+/// exits right away.
+pub fn exit_on_signal(
+    config: Arc<Mutex<Arc<Config>>>,
+    executor: Arc<Executor>,
+) -> anyhow::Result<()> {
+    let exiting = AtomicBool::new(false);
+    on_exit_signal(move || {
+        if exiting.swap(true, Ordering::SeqCst) {
+            println!("Exiting without waiting for the `on_exit` shortcuts");
+            exit(1);
+        }
+
+        let config = config.lock().unwrap().clone();
+        let executor = executor.clone();
+        // Wait for the shortcuts on another thread to still receive a second signal meanwhile
+        thread::spawn(move || {
+            println!("Exiting, running the `on_exit` shortcuts");
+            lifecycle_shortcuts(&config, Trigger::OnExit)
+                .for_each(|shortcut| executor.run(shortcut, &config));
+            if !executor.wait_idle_timeout(ON_EXIT_TIMEOUT) {
+                println!(
+                    "Exiting, shortcuts are still running after {}s",
+                    ON_EXIT_TIMEOUT.as_secs()
+                );
+                exit(1);
+            }
+            exit(0);
+        });
+    })
+}
+
+/// Exit the process after removing the control socket, the other resources are released by the
+/// system.
+fn exit(code: i32) -> ! {
+    #[cfg(unix)]
+    crate::ipc::remove_socket();
+    process::exit(code)
+}
+
+fn lifecycle_shortcuts(config: &Config, trigger: Trigger) -> impl Iterator<Item = &Shortcut> {
+    config
+        .all_shortcuts()
+        .filter(move |shortcut| shortcut.trigger == trigger)
+}
+
+#[cfg(unix)]
+fn on_exit_signal<F>(mut on_signal: F) -> anyhow::Result<()>
+where
+    F: FnMut() + Send + 'static,
+{
+    use signal_hook::consts::{SIGINT, SIGTERM};
+    use signal_hook::iterator::Signals;
+
+    let mut signals = Signals::new([SIGINT, SIGTERM])
+        .context("Could not register SIGINT and SIGTERM handlers")?;
+    thread::spawn(move || {
+        for _ in signals.forever() {
+            on_signal();
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn on_exit_signal<F>(on_signal: F) -> anyhow::Result<()>
+where
+    F: FnMut() + Send + 'static,
+{
+    ctrlc::set_handler(on_signal).context("Could not register Ctrl+C handler")
+}
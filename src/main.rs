@@ -1,6 +1,206 @@
-use shortcut_hero::{run, Config};
+use std::collections::HashMap;
+use std::path::PathBuf;
 
-fn main() {
-    let config = Config::load_config();
-    run(&config);
+use anyhow::anyhow;
+use clap::{Parser, Subcommand};
+
+use shortcut_hero::hotkey::{Shortcut, TriggerContext};
+#[cfg(unix)]
+use shortcut_hero::ipc;
+use shortcut_hero::validation::{has_errors, validate_config};
+use shortcut_hero::{resolve_config_path, run, Config};
+
+/// Bind keyboard shortcuts to any actions
+#[derive(Parser)]
+#[command(author, version, about)]
+struct Cli {
+    /// Path to the configuration file (`.json`, `.toml`, `.yaml` or `.yml`). Defaults to
+    /// `shortcut-hero.json` in the current directory if it exists, else
+    /// `$XDG_CONFIG_HOME/shortcut-hero/shortcut-hero.json`.
+    #[arg(short, long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Register the keyboard shortcuts and wait for them to be pressed (default).
+    Run,
+    /// Check the configuration file without running anything.
+    Validate,
+    /// List the configured keyboard shortcuts.
+    List,
+    /// Create a configuration file with some example shortcuts.
+    Init {
+        /// Overwrite the configuration file if it already exists.
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Convert the configuration file to another format (JSON, TOML or YAML).
+    Convert {
+        /// Path of the converted configuration file, the format is picked from its extension
+        /// (`.json`, `.toml`, `.yaml` or `.yml`).
+        output: PathBuf,
+        /// Overwrite the output file if it already exists.
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Print the JSON Schema of the configuration file, for editors autocompletion and validation.
+    Schema,
+    /// Run a shortcut of the running Shortcut Hero, without pressing its keys, and print its
+    /// output.
+    Trigger {
+        /// Id of the shortcut, its index in `keyboard_shortcuts` (starts at 0) or its description.
+        shortcut: String,
+        /// Initial `{{input}}` of the shortcut.
+        #[arg(short, long, default_value = "")]
+        input: String,
+        /// Set a variable of the shortcut, e.g. `--var language=French`. Can be repeated.
+        #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_variable)]
+        variables: Vec<(String, String)>,
+        /// Run the shortcut in this process instead, with the configuration file.
+        #[arg(long)]
+        local: bool,
+    },
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let config_path = resolve_config_path(cli.config.as_deref());
+
+    match cli.command.unwrap_or(Command::Run) {
+        Command::Run => {
+            let config = Config::load_and_upgrade_config(&config_path)?;
+            run(&config_path, &config)?;
+        }
+
+        Command::Validate => {
+            let config = Config::read_config(&config_path)?;
+            let diagnostics = validate_config(&config);
+            diagnostics
+                .iter()
+                .for_each(|diagnostic| println!("{diagnostic}"));
+            if has_errors(&diagnostics) {
+                return Err(anyhow!("Configuration file {config_path:?} is invalid"));
+            }
+            println!("Configuration file {config_path:?} is valid");
+        }
+
+        Command::List => {
+            let config = Config::load_config(&config_path)?;
+            print_shortcuts(&config.keyboard_shortcuts);
+            for (name, mode) in &config.modes {
+                println!("\nMode `{name}` - {}", mode.description);
+                print_shortcuts(&mode.keyboard_shortcuts);
+            }
+        }
+
+        Command::Init { force } => {
+            if config_path.exists() && !force {
+                return Err(anyhow!(
+                    "Config file {config_path:?} already exists, use `--force` to overwrite it"
+                ));
+            }
+            Config::init_config(&config_path)?;
+            println!("Created config file {config_path:?}");
+        }
+
+        Command::Convert { output, force } => {
+            if output.exists() && !force {
+                return Err(anyhow!(
+                    "File {output:?} already exists, use `--force` to overwrite it"
+                ));
+            }
+            let config = Config::read_config_without_includes(&config_path)?;
+            Config::save_config(&config, &output)?;
+            println!("Converted config file {config_path:?} to {output:?}");
+        }
+
+        Command::Schema => {
+            let schema = Config::json_schema();
+            println!("{}", serde_json::to_string_pretty(&schema)?);
+        }
+
+        Command::Trigger {
+            shortcut,
+            input,
+            variables,
+            local,
+        } => {
+            let variables = variables.into_iter().collect();
+            let output = if local {
+                let config = Config::load_config(&config_path)?;
+                let shortcut = config
+                    .find_shortcut(&shortcut)
+                    .ok_or_else(|| anyhow!("No shortcut found matching `{shortcut}`"))?;
+                shortcut
+                    .trigger_with(&config, &TriggerContext::default(), &input, variables)?
+                    .output
+            } else {
+                trigger_remote(shortcut, input, variables)?
+            };
+            println!("{output}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse a `NAME=VALUE` variable.
+fn parse_variable(variable: &str) -> Result<(String, String), String> {
+    variable
+        .split_once('=')
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .ok_or_else(|| format!("Invalid variable `{variable}`, expected `NAME=VALUE`"))
+}
+
+/// Trigger a shortcut through the control socket of the running Shortcut Hero.
+#[cfg(unix)]
+fn trigger_remote(
+    shortcut: String,
+    input: String,
+    variables: HashMap<String, String>,
+) -> anyhow::Result<String> {
+    let request = ipc::Request::Trigger {
+        id: shortcut,
+        input,
+        variables,
+    };
+    match ipc::send(&ipc::default_socket_path(), &request)? {
+        ipc::Response::Triggered { output, .. } => Ok(output),
+        ipc::Response::Error { message } => Err(anyhow!(message)),
+        response => Err(anyhow!("Unexpected response {response:?}")),
+    }
+}
+
+#[cfg(not(unix))]
+fn trigger_remote(
+    _shortcut: String,
+    _input: String,
+    _variables: HashMap<String, String>,
+) -> anyhow::Result<String> {
+    Err(anyhow!(
+        "Triggering the running Shortcut Hero is only supported on Unix, use `--local`"
+    ))
+}
+
+fn print_shortcuts(shortcuts: &[Shortcut]) {
+    for (i, shortcut) in shortcuts.iter().enumerate() {
+        let label = match &shortcut.id {
+            Some(id) => format!("[{i}] {id}:"),
+            None => format!("[{i}]"),
+        };
+        let mut triggers = vec![shortcut.format_keys()];
+        triggers.extend(shortcut.schedule.iter().map(ToString::to_string));
+        triggers.extend(shortcut.watch.iter().map(ToString::to_string));
+        triggers.retain(|trigger| !trigger.is_empty());
+        println!(
+            "{label} {} ({} actions) - {}",
+            triggers.join(", "),
+            shortcut.actions.len(),
+            shortcut.description
+        );
+    }
 }
@@ -0,0 +1,119 @@
+use anyhow::anyhow;
+use serde_json::Value;
+
+/// Current version of the configuration format, bump it when adding a migration
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Actions renamed over time, `(old name, new name)`
+const ACTION_ALIASES: [(&str, &str); 1] = [("openai_ask_chatgpt", "ask_chatgpt")];
+
+/// Upgrade a configuration from `version` to `version + 1`, returns the list of changes
+type Migration = fn(&mut Value) -> Vec<String>;
+
+/// Migrations to apply, in order, the migration at index `i` upgrades from version `i`
+const MIGRATIONS: [Migration; CONFIG_VERSION as usize] = [migrate_v0_to_v1];
+
+/// Get the version of a configuration in its raw form, configurations without a version were
+/// written before versioning was introduced (version `0`).
+pub fn get_config_version(config: &Value) -> anyhow::Result<u32> {
+    match config.get("version") {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .map(|version| version as u32)
+            .ok_or_else(|| anyhow!("`version` must be a positive integer, got `{version}`")),
+    }
+}
+
+fn get_supported_config_version(config: &Value) -> anyhow::Result<u32> {
+    let version = get_config_version(config)?;
+    if version > CONFIG_VERSION {
+        return Err(anyhow!(
+            "Config version {version} is newer than the supported version {CONFIG_VERSION}, \
+            please upgrade Shortcut Hero"
+        ));
+    }
+    Ok(version)
+}
+
+/// Whether the configuration in its raw form must be upgraded to the current version, fails if
+/// the configuration is newer than the current version.
+pub fn needs_migration(config: &Value) -> anyhow::Result<bool> {
+    let version = get_supported_config_version(config)?;
+    Ok(version < CONFIG_VERSION)
+}
+
+/// Upgrade a configuration in its raw form to the current version, returns the list of changes.
+pub fn migrate_config(config: &mut Value) -> anyhow::Result<Vec<String>> {
+    let version = get_supported_config_version(config)?;
+    let config_object = config
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("Config must be an object"))?;
+    config_object.insert("version".to_string(), Value::from(version));
+
+    let mut changes = Vec::new();
+    for (from_version, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        changes.extend(migration(config));
+        config["version"] = Value::from(from_version + 1);
+        changes.push(format!(
+            "Upgraded config from version {from_version} to {}",
+            from_version + 1
+        ));
+    }
+    Ok(changes)
+}
+
+/// Call `migrate_action` on every action of every shortcut, with the shortcut and step indexes
+fn for_each_action(config: &mut Value, mut migrate_action: impl FnMut(usize, usize, &mut Value)) {
+    let Some(shortcuts) = config
+        .get_mut("keyboard_shortcuts")
+        .and_then(|shortcuts| shortcuts.as_array_mut())
+    else {
+        return;
+    };
+    for (shortcut_index, shortcut) in shortcuts.iter_mut().enumerate() {
+        let Some(actions) = shortcut
+            .get_mut("actions")
+            .and_then(|actions| actions.as_array_mut())
+        else {
+            continue;
+        };
+        for (step_index, action) in actions.iter_mut().enumerate() {
+            migrate_action(shortcut_index, step_index, action);
+        }
+    }
+}
+
+/// - Rename the `openai_ask_chatgpt` action to `ask_chatgpt`
+/// - Rename the `step_relative` parameter of `go_to_step_relative` to `step`
+fn migrate_v0_to_v1(config: &mut Value) -> Vec<String> {
+    let mut changes = Vec::new();
+    for_each_action(config, |shortcut_index, step_index, action| {
+        let Some(action) = action.as_object_mut() else {
+            return;
+        };
+
+        let name = action.get("action").and_then(|name| name.as_str());
+        let alias = ACTION_ALIASES
+            .iter()
+            .find(|(old_name, _)| Some(*old_name) == name);
+        if let Some((old_name, new_name)) = alias {
+            action.insert("action".to_string(), Value::from(*new_name));
+            changes.push(format!(
+                "[shortcut {shortcut_index}, step {step_index}] Renamed action `{old_name}` to `{new_name}`"
+            ));
+        }
+
+        let is_go_to_step_relative =
+            action.get("action").and_then(|name| name.as_str()) == Some("go_to_step_relative");
+        if is_go_to_step_relative && !action.contains_key("step") {
+            if let Some(step) = action.remove("step_relative") {
+                action.insert("step".to_string(), step);
+                changes.push(format!(
+                    "[shortcut {shortcut_index}, step {step_index}] Renamed parameter `step_relative` to `step`"
+                ));
+            }
+        }
+    });
+    changes
+}
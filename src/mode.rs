@@ -0,0 +1,25 @@
+use std::sync::Mutex;
+
+/// Name of the mode of the top-level `keyboard_shortcuts`, active on startup
+pub const DEFAULT_MODE: &str = "normal";
+
+/// Name of the variable holding the current mode in actions (`{{mode}}`)
+pub const MODE_VARIABLE: &str = "mode";
+
+/// Current mode, empty until the first mode switch
+static CURRENT_MODE: Mutex<String> = Mutex::new(String::new());
+
+/// Get the name of the current mode, only the shortcuts of this mode are active.
+pub fn current_mode() -> String {
+    let current_mode = CURRENT_MODE.lock().unwrap();
+    if current_mode.is_empty() {
+        DEFAULT_MODE.to_string()
+    } else {
+        current_mode.clone()
+    }
+}
+
+/// Change the current mode, the mode must exist in the configuration.
+pub fn set_current_mode(mode: &str) {
+    *CURRENT_MODE.lock().unwrap() = mode.to_string();
+}
@@ -0,0 +1,117 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::Config;
+
+/// Editors usually write a file in multiple steps, wait for the writes to settle before reloading
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Watch the configuration file and its included files, and call `on_reload` with the new
+/// configuration when one of them changes, or when the process receives `SIGHUP` (Unix only).
+///
+/// A new file matching an `include` glob pattern is only picked on the next reload.
+///
+/// The new configuration is validated before calling `on_reload`. If it is invalid, the error is
+/// reported and `on_reload` is not called, so the previous configuration stays active.
+pub fn watch_config<F>(config_path: &Path, config: &Config, on_reload: F) -> anyhow::Result<()>
+where
+    F: Fn(Config) + Send + 'static,
+{
+    let config_path = config_path
+        .canonicalize()
+        .with_context(|| format!("Could not resolve config file path {config_path:?}"))?;
+    let (tx, rx) = mpsc::channel::<()>();
+
+    // Watch the parent directories instead of the files themselves, editors often replace a file
+    // when saving, which would silently end a watch on the file. Only the changes of the loaded
+    // files trigger a reload, not the other files of these directories
+    let loaded_files = Arc::new(Mutex::new(config.loaded_files.clone()));
+    let watched_files = loaded_files.clone();
+    let watcher_tx = tx.clone();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event {
+            let watched_files = watched_files.lock().unwrap();
+            let is_loaded_file = event.paths.iter().any(|path| watched_files.contains(path));
+            let is_change =
+                event.kind.is_create() || event.kind.is_modify() || event.kind.is_remove();
+            if is_loaded_file && is_change {
+                watcher_tx.send(()).ok();
+            }
+        }
+    })?;
+    let mut watched_dirs = HashSet::new();
+    watch_config_dirs(&mut watcher, &mut watched_dirs, config)?;
+
+    #[cfg(unix)]
+    watch_sighup(tx)?;
+
+    thread::spawn(move || {
+        while rx.recv().is_ok() {
+            thread::sleep(RELOAD_DEBOUNCE);
+            while rx.try_recv().is_ok() {}
+
+            match reload_config(&config_path) {
+                Ok(config) => {
+                    if let Err(e) = watch_config_dirs(&mut watcher, &mut watched_dirs, &config) {
+                        eprintln!("{e:#}");
+                    }
+                    *loaded_files.lock().unwrap() = config.loaded_files.clone();
+                    on_reload(config)
+                }
+                Err(e) => {
+                    eprintln!("Failed to reload config, keeping the previous configuration - {e:#}")
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Watch the directories of the files the configuration was loaded from, if not already watched
+fn watch_config_dirs(
+    watcher: &mut RecommendedWatcher,
+    watched_dirs: &mut HashSet<PathBuf>,
+    config: &Config,
+) -> anyhow::Result<()> {
+    for loaded_file in &config.loaded_files {
+        let dir = loaded_file.parent().unwrap_or(Path::new(".")).to_path_buf();
+        if watched_dirs.contains(&dir) {
+            continue;
+        }
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Could not watch config directory {dir:?}"))?;
+        watched_dirs.insert(dir);
+    }
+    Ok(())
+}
+
+fn reload_config(config_path: &Path) -> anyhow::Result<Config> {
+    // Do not let `load_and_upgrade_config` create an example config if the file was removed in the meantime
+    if !config_path.exists() {
+        return Err(anyhow!("Config file {config_path:?} was removed"));
+    }
+    println!("\nReloading config from {config_path:?}");
+    Config::load_and_upgrade_config(config_path)
+}
+
+#[cfg(unix)]
+fn watch_sighup(tx: mpsc::Sender<()>) -> anyhow::Result<()> {
+    use signal_hook::consts::SIGHUP;
+    use signal_hook::iterator::Signals;
+
+    let mut signals = Signals::new([SIGHUP]).context("Could not register SIGHUP handler")?;
+    thread::spawn(move || {
+        for _ in signals.forever() {
+            tx.send(()).ok();
+        }
+    });
+    Ok(())
+}
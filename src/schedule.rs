@@ -0,0 +1,194 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Local};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::dispatch::ShortcutRunner;
+use crate::executor::CancelToken;
+use crate::mode::current_mode;
+use crate::Config;
+
+/// Longest wait of the scheduler before checking the time again, so the runs are not late after
+/// the computer wakes up from sleep or its clock changes.
+const SCHEDULER_MAX_WAIT: Duration = Duration::from_secs(60);
+
+/// Longest interval of an `every_ms` schedule, one year.
+pub const MAX_EVERY_MS: u64 = 365 * 24 * 60 * 60 * 1000;
+
+/// When to run a shortcut on a schedule, without pressing its keys.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Schedule {
+    /// Every given number of milliseconds, at most one year. The first run is one interval after
+    /// the start.
+    EveryMs(u64),
+    /// On a cron expression in local time, e.g. `0 9 * * Mon-Fri` for 9 AM on weekdays. A sixth
+    /// leading field for the seconds is supported, e.g. `*/30 * * * * *`.
+    Cron(String),
+}
+
+impl Schedule {
+    /// Get the time of the next run after `after`, `None` if the schedule has no next run.
+    pub fn next_run(&self, after: DateTime<Local>) -> anyhow::Result<Option<DateTime<Local>>> {
+        match self {
+            Schedule::EveryMs(every_ms) => {
+                let every = i64::try_from(*every_ms)
+                    .map(chrono::Duration::milliseconds)
+                    .with_context(|| format!("`every_ms` {every_ms} is too large"))?;
+                let next_run = after.checked_add_signed(every).ok_or_else(|| {
+                    anyhow!("Next run {every_ms}ms after {after} is out of the supported dates")
+                })?;
+                Ok(Some(next_run))
+            }
+            Schedule::Cron(expression) => Ok(parse_cron(expression)?.after(&after).next()),
+        }
+    }
+
+    /// Get the time of the run following the run `scheduled` at a time that passed, so the runs
+    /// do not drift with the time the runs take. The runs missed before `now` are skipped.
+    pub fn next_run_since(
+        &self,
+        scheduled: DateTime<Local>,
+        now: DateTime<Local>,
+    ) -> anyhow::Result<Option<DateTime<Local>>> {
+        match self {
+            Schedule::EveryMs(every_ms) => {
+                let every_ms = i64::try_from(*every_ms)
+                    .with_context(|| format!("`every_ms` {every_ms} is too large"))?;
+                let missed_ms = (now - scheduled)
+                    .num_milliseconds()
+                    .checked_div(every_ms)
+                    .ok_or_else(|| anyhow!("`every_ms` must be greater than 0"))?
+                    * every_ms;
+                self.next_run(scheduled + chrono::Duration::milliseconds(missed_ms))
+            }
+            // The cron runs are at fixed times, the next one after now is already the right one
+            Schedule::Cron(_) => self.next_run(now),
+        }
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Schedule::EveryMs(every_ms) => write!(f, "every {every_ms}ms"),
+            Schedule::Cron(expression) => write!(f, "cron `{expression}`"),
+        }
+    }
+}
+
+/// Parse a cron expression of 5 fields (minute to day of week), or 6 and 7 fields starting with
+/// the seconds and ending with the year. The days of week must be names, e.g. `Mon-Fri`.
+pub fn parse_cron(expression: &str) -> anyhow::Result<cron::Schedule> {
+    let with_seconds = match expression.split_whitespace().count() {
+        5 => format!("0 {expression}"),
+        6 | 7 => expression.to_string(),
+        count => {
+            return Err(anyhow!(
+                "Cron expression `{expression}` has {count} fields, expected 5 (minute hour \
+                 day-of-month month day-of-week)"
+            ))
+        }
+    };
+
+    // The `cron` crate numbers the days of week from 1 for Sunday, standard cron from 0 for Sunday
+    // and 1 for Monday, the numbers would silently run on other days than expected
+    let day_of_week = with_seconds.split_whitespace().nth(5).unwrap_or_default();
+    let has_day_number = day_of_week.split(',').any(|days| {
+        let days = days.split('/').next().unwrap_or_default();
+        days.contains(|c: char| c.is_ascii_digit())
+    });
+    if has_day_number {
+        return Err(anyhow!(
+            "Day of week `{day_of_week}` of cron expression `{expression}` must use day names, \
+             e.g. `Mon-Fri` or `Sun`, the day numbers are not the ones of the standard cron"
+        ));
+    }
+
+    cron::Schedule::from_str(&with_seconds)
+        .with_context(|| format!("Invalid cron expression `{expression}`"))
+}
+
+/// Runs the shortcuts with a `schedule`, on a thread of its own.
+///
+/// A scheduled shortcut only runs while its mode is active, the runs missed in another mode are
+/// skipped.
+pub struct Scheduler {
+    stop: CancelToken,
+}
+
+/// Next run of a scheduled shortcut.
+struct NextRun {
+    mode: String,
+    shortcut_index: usize,
+    at: DateTime<Local>,
+}
+
+impl Scheduler {
+    /// Start running the scheduled shortcuts of every mode with `runner`.
+    pub fn start(config: Arc<Config>, runner: Arc<dyn ShortcutRunner>) -> Scheduler {
+        let stop = CancelToken::default();
+        let scheduler_stop = stop.clone();
+        thread::spawn(move || run_schedules(&config, runner.as_ref(), &scheduler_stop));
+        Scheduler { stop }
+    }
+
+    pub fn stop(&self) {
+        self.stop.cancel();
+    }
+}
+
+fn run_schedules(config: &Arc<Config>, runner: &dyn ShortcutRunner, stop: &CancelToken) {
+    let now = Local::now();
+    let mut next_runs = Vec::new();
+    for (mode, shortcuts) in config.shortcuts_by_mode() {
+        for (shortcut_index, shortcut) in shortcuts.iter().enumerate() {
+            if let Some(at) = shortcut.schedule.as_ref().and_then(|s| next_run(s, now)) {
+                next_runs.push(NextRun {
+                    mode: mode.to_string(),
+                    shortcut_index,
+                    at,
+                });
+            }
+        }
+    }
+
+    while !next_runs.is_empty() {
+        let next_at = next_runs.iter().map(|next_run| next_run.at).min().unwrap();
+        let wait = (next_at - Local::now()).to_std().unwrap_or_default();
+        if stop.sleep(wait.min(SCHEDULER_MAX_WAIT)).is_err() {
+            return;
+        }
+
+        let now = Local::now();
+        let mode = current_mode();
+        next_runs.retain_mut(|next| {
+            if next.at > now {
+                return true;
+            }
+            let shortcut = &config.shortcuts_for_mode(&next.mode)[next.shortcut_index];
+            if next.mode == mode {
+                runner.run(shortcut, config);
+            }
+            let schedule = shortcut.schedule.as_ref().unwrap();
+            match schedule.next_run_since(next.at, now).ok().flatten() {
+                Some(at) => {
+                    next.at = at;
+                    true
+                }
+                None => false,
+            }
+        });
+    }
+}
+
+/// Get the time of the next run, errors were already reported by the validation.
+fn next_run(schedule: &Schedule, after: DateTime<Local>) -> Option<DateTime<Local>> {
+    schedule.next_run(after).ok().flatten()
+}
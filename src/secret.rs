@@ -0,0 +1,119 @@
+use std::fmt::{Debug, Formatter};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{env, fs};
+
+use anyhow::{anyhow, Context};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// A credential, either written directly in the configuration file or resolved from another
+/// source when it is needed.
+///
+/// Secrets are always redacted when printed.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum Secret {
+    /// Value written directly in the configuration file.
+    Plain(String),
+    /// Read the value from an environment variable, e.g. `{"from_env": "OPENAI_API_KEY"}`.
+    FromEnv {
+        /// Name of the environment variable.
+        from_env: String,
+    },
+    /// Read the value from a file, surrounding whitespace is trimmed.
+    FromFile {
+        /// Path of the file containing the value, a relative path is relative to the
+        /// configuration file.
+        from_file: PathBuf,
+    },
+    /// Read the value from the standard output of a command, surrounding whitespace is trimmed,
+    /// e.g. `{"from_command": ["pass", "show", "openai"]}`.
+    FromCommand {
+        /// Command to execute followed by its arguments.
+        from_command: Vec<String>,
+    },
+}
+
+impl Secret {
+    /// Get the value of the secret.
+    pub fn resolve(&self) -> anyhow::Result<String> {
+        match self {
+            Secret::Plain(value) => Ok(value.clone()),
+            Secret::FromEnv { from_env } => env::var(from_env).with_context(|| {
+                format!("Could not read secret from environment variable `{from_env}`")
+            }),
+            Secret::FromFile { from_file } => fs::read_to_string(from_file)
+                .map(|value| value.trim().to_string())
+                .with_context(|| format!("Could not read secret from file {from_file:?}")),
+            Secret::FromCommand { from_command } => {
+                let (command, args) = from_command
+                    .split_first()
+                    .ok_or_else(|| anyhow!("Could not read secret, `from_command` is empty"))?;
+                let output = Command::new(command)
+                    .args(args)
+                    .output()
+                    .with_context(|| format!("Could not read secret from command `{command}`"))?;
+                if !output.status.success() {
+                    return Err(anyhow!(
+                        "Could not read secret, command `{command}` failed: {}",
+                        String::from_utf8_lossy(&output.stderr)
+                    ));
+                }
+                Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+            }
+        }
+    }
+
+    /// Make a relative `from_file` path relative to `base_dir` instead, the directory of the
+    /// configuration file.
+    pub fn resolve_relative_path(&mut self, base_dir: &Path) {
+        if let Secret::FromFile { from_file } = self {
+            if from_file.is_relative() {
+                *from_file = base_dir.join(&*from_file);
+            }
+        }
+    }
+
+    /// Whether the secret is a plain value left empty.
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Secret::Plain(value) if value.trim().is_empty())
+    }
+
+    /// Check that the secret can probably be resolved, without running any command.
+    pub fn check_available(&self) -> anyhow::Result<()> {
+        match self {
+            Secret::Plain(value) if value.trim().is_empty() => Err(anyhow!("Secret is empty")),
+            Secret::FromEnv { from_env } if env::var_os(from_env).is_none() => {
+                Err(anyhow!("Environment variable `{from_env}` is not set"))
+            }
+            Secret::FromFile { from_file } if !from_file.exists() => {
+                Err(anyhow!("Secret file {from_file:?} does not exist"))
+            }
+            Secret::FromCommand { from_command } if from_command.is_empty() => {
+                Err(anyhow!("`from_command` is empty"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for Secret {
+    fn default() -> Self {
+        Secret::Plain("".to_string())
+    }
+}
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Secret::Plain(value) if value.is_empty() => write!(f, "Secret(\"\")"),
+            Secret::Plain(_) => write!(f, "Secret(<redacted>)"),
+            Secret::FromEnv { from_env } => write!(f, "Secret(from_env: {from_env:?})"),
+            Secret::FromFile { from_file } => write!(f, "Secret(from_file: {from_file:?})"),
+            Secret::FromCommand { from_command } => {
+                write!(f, "Secret(from_command: {from_command:?})")
+            }
+        }
+    }
+}
@@ -0,0 +1,591 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+use crate::actions::{parse_step, parse_step_relative, Action};
+use crate::evaluation::{NumberOperator, StringOperator};
+use crate::hotkey::{KeyboardKey, Shortcut, Trigger, MAX_CALL_DEPTH};
+use crate::mode::{DEFAULT_MODE, MODE_VARIABLE};
+use crate::schedule::{parse_cron, Schedule, MAX_EVERY_MS};
+use crate::secret::Secret;
+use crate::Config;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The configuration can not be used.
+    Error,
+    /// The configuration can be used but is probably not doing what is expected.
+    Warning,
+}
+
+/// A problem found in the configuration.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Mode of the shortcut, `None` for the top-level `keyboard_shortcuts`.
+    pub mode: Option<String>,
+    /// Index of the shortcut in its `keyboard_shortcuts`, `None` if the problem is not related to
+    /// a shortcut.
+    pub shortcut_index: Option<usize>,
+    /// Id of the shortcut, if it has one.
+    pub shortcut_id: Option<String>,
+    /// Index of the action in the shortcut's `actions`, `None` if the problem is not related to
+    /// an action.
+    pub step_index: Option<usize>,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.severity {
+            Severity::Error => write!(f, "error")?,
+            Severity::Warning => write!(f, "warning")?,
+        }
+        if let Some(mode) = &self.mode {
+            write!(f, " [mode {mode}]")?;
+        }
+        if let Some(shortcut_index) = self.shortcut_index {
+            write!(f, " [shortcut {shortcut_index}")?;
+            if let Some(shortcut_id) = &self.shortcut_id {
+                write!(f, " `{shortcut_id}`")?;
+            }
+            if let Some(step_index) = self.step_index {
+                write!(f, ", step {step_index}")?;
+            }
+            write!(f, "]")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Check every shortcut and action of the configuration, returns all the problems found.
+///
+/// Parameters using variables tags (`{{...}}`) can only be checked when the shortcut is triggered,
+/// they are skipped here.
+pub fn validate_config(config: &Config) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for name in config.variables.keys() {
+        if let Err(message) = check_initial_variable_name(name) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                mode: None,
+                shortcut_index: None,
+                shortcut_id: None,
+                step_index: None,
+                message,
+            });
+        }
+    }
+
+    if config.sequence_timeout_ms == Some(0) {
+        diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            mode: None,
+            shortcut_index: None,
+            shortcut_id: None,
+            step_index: None,
+            message: "`sequence_timeout_ms` must be greater than 0".to_string(),
+        });
+    }
+    if config.max_concurrent_runs == Some(0) {
+        diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            mode: None,
+            shortcut_index: None,
+            shortcut_id: None,
+            step_index: None,
+            message: "`max_concurrent_runs` must be greater than 0".to_string(),
+        });
+    }
+
+    config
+        .keyboard_shortcuts
+        .iter()
+        .enumerate()
+        .for_each(|(i, shortcut)| validate_shortcut(config, None, i, shortcut, &mut diagnostics));
+    check_key_conflicts(None, &config.keyboard_shortcuts, &mut diagnostics);
+    check_duplicate_ids(config, &mut diagnostics);
+
+    for (name, mode) in &config.modes {
+        mode.keyboard_shortcuts
+            .iter()
+            .enumerate()
+            .for_each(|(i, shortcut)| {
+                validate_shortcut(config, Some(name), i, shortcut, &mut diagnostics)
+            });
+        check_key_conflicts(Some(name), &mode.keyboard_shortcuts, &mut diagnostics);
+
+        let mut called_ids = HashSet::new();
+        let can_leave_mode = mode
+            .keyboard_shortcuts
+            .iter()
+            .any(|shortcut| can_switch_mode(config, shortcut, 0, &mut called_ids));
+        if !can_leave_mode {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                mode: Some(name.clone()),
+                shortcut_index: None,
+                shortcut_id: None,
+                step_index: None,
+                message: "No shortcut of this mode switches to another mode, \
+                    there is no way back once switched to it"
+                    .to_string(),
+            });
+        }
+    }
+    diagnostics
+}
+
+/// Whether the actions of the shortcut, or of the shortcuts it calls up to the maximum call depth,
+/// switch to another mode. Every called shortcut is only looked at once.
+fn can_switch_mode<'a>(
+    config: &'a Config,
+    shortcut: &'a Shortcut,
+    depth: usize,
+    called_ids: &mut HashSet<&'a str>,
+) -> bool {
+    shortcut.actions.iter().any(|action| match action {
+        Action::SwitchMode { .. } => true,
+        // The id is only known when running, it may call a shortcut switching the mode
+        Action::CallShortcut { id, .. } if !is_literal(id) => true,
+        Action::CallShortcut { id, .. } => {
+            depth < MAX_CALL_DEPTH
+                && called_ids.insert(id)
+                && config
+                    .find_shortcut_by_id(id)
+                    .is_some_and(|called| can_switch_mode(config, called, depth + 1, called_ids))
+        }
+        _ => false,
+    })
+}
+
+/// Whether the diagnostics contain at least one error.
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+}
+
+/// Report the shortcuts using the id of another shortcut, in any mode, the shortcuts are found by
+/// id regardless of the mode.
+fn check_duplicate_ids(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    let mut first_uses: HashMap<&str, (&str, usize)> = HashMap::new();
+    for (mode, shortcuts) in config.shortcuts_by_mode() {
+        for (i, shortcut) in shortcuts.iter().enumerate() {
+            let Some(id) = shortcut.id.as_deref() else {
+                continue;
+            };
+            let Some(&(first_mode, first_index)) = first_uses.get(id) else {
+                first_uses.insert(id, (mode, i));
+                continue;
+            };
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                mode: (mode != DEFAULT_MODE).then(|| mode.to_string()),
+                shortcut_index: Some(i),
+                shortcut_id: Some(id.to_string()),
+                step_index: None,
+                message: format!(
+                    "Id `{id}` is already used by shortcut {first_index} of mode `{first_mode}`, \
+                     ids must be unique across every mode"
+                ),
+            });
+        }
+    }
+}
+
+/// Report the shortcuts of a mode triggered by the same keys, or also triggered by the keys of
+/// another shortcut.
+fn check_key_conflicts(
+    mode: Option<&String>,
+    shortcuts: &[Shortcut],
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for (i, shortcut) in shortcuts.iter().enumerate() {
+        let sequence = shortcut.key_sequence();
+        if sequence.is_empty() {
+            continue;
+        }
+
+        for (j, other) in shortcuts.iter().enumerate() {
+            let other_sequence = other.key_sequence();
+            // Different triggers on the same keys is how a key does something different when
+            // tapped or held
+            if i == j
+                || shortcut.trigger != other.trigger
+                || !is_sequence_covered(&sequence, &other_sequence)
+            {
+                continue;
+            }
+
+            let message = if is_sequence_covered(&other_sequence, &sequence) {
+                // Only report the same keys once, on the last shortcut
+                if j > i {
+                    continue;
+                }
+                format!(
+                    "Same keys `{}` as shortcut {j}, both run when pressed",
+                    shortcut.format_keys()
+                )
+            } else if !shortcut.exact_match {
+                format!(
+                    "Keys `{}` also trigger when pressing `{}` of shortcut {j}, set \
+                     `exact_match` to prevent it",
+                    shortcut.format_keys(),
+                    other.format_keys()
+                )
+            } else {
+                continue;
+            };
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                mode: mode.cloned(),
+                shortcut_index: Some(i),
+                shortcut_id: shortcut.id.clone(),
+                step_index: None,
+                message,
+            });
+        }
+    }
+}
+
+/// Whether pressing the `other` sequence can also press `sequence`: same number of combos, and
+/// every key of a combo matches a key of the other combo.
+fn is_sequence_covered(sequence: &[&[KeyboardKey]], other: &[&[KeyboardKey]]) -> bool {
+    sequence.len() == other.len()
+        && sequence.iter().zip(other).all(|(combo, other_combo)| {
+            combo.iter().all(|key| {
+                key.physical_keys()
+                    .iter()
+                    .any(|physical_key| other_combo.iter().any(|k| k.matches(*physical_key)))
+            })
+        })
+}
+
+fn validate_shortcut(
+    config: &Config,
+    mode: Option<&String>,
+    shortcut_index: usize,
+    shortcut: &Shortcut,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut report = |severity: Severity, step_index: Option<usize>, message: String| {
+        diagnostics.push(Diagnostic {
+            severity,
+            mode: mode.cloned(),
+            shortcut_index: Some(shortcut_index),
+            shortcut_id: shortcut.id.clone(),
+            step_index,
+            message,
+        })
+    };
+
+    // A shortcut without keys can still be run with `call_shortcut` if it has an id
+    if !shortcut.keys.is_empty() && !shortcut.sequence.is_empty() {
+        report(
+            Severity::Error,
+            None,
+            "Shortcut cannot have both `keys` and `sequence` defined".to_string(),
+        );
+    }
+    match shortcut.trigger {
+        Trigger::Hold { min_duration_ms: 0 } => report(
+            Severity::Error,
+            None,
+            "`min_duration_ms` of the `hold` trigger must be greater than 0".to_string(),
+        ),
+        Trigger::DoubleTap { within_ms: 0 } => report(
+            Severity::Error,
+            None,
+            "`within_ms` of the `double_tap` trigger must be greater than 0".to_string(),
+        ),
+        _ => {}
+    }
+    if shortcut.repeat && (shortcut.trigger != Trigger::Press || !shortcut.sequence.is_empty()) {
+        report(
+            Severity::Warning,
+            None,
+            "`repeat` only applies to `keys` triggered on `press`, it is ignored".to_string(),
+        );
+    }
+    for (i, combo) in shortcut.sequence.iter().enumerate() {
+        if combo.is_empty() {
+            report(
+                Severity::Error,
+                None,
+                format!("Key combo {i} of `sequence` is empty"),
+            );
+        }
+    }
+    if !shortcut.has_trigger() && shortcut.id.is_none() {
+        report(
+            Severity::Error,
+            None,
+            "Shortcut does not have keyboard keys, a schedule or a trigger defined".to_string(),
+        );
+    }
+    if let Some(watch) = &shortcut.watch {
+        if let Err(e) = watch.glob_pattern() {
+            report(Severity::Error, None, format!("{e:#}"));
+        }
+        if watch.events.is_empty() {
+            report(
+                Severity::Error,
+                None,
+                "`events` of the `watch` is empty, the shortcut never runs".to_string(),
+            );
+        }
+        if !watch.expanded_path().exists() {
+            report(
+                Severity::Warning,
+                None,
+                format!("Watched path `{}` does not exist", watch.path),
+            );
+        }
+    }
+    if shortcut.trigger.is_lifecycle() && !shortcut.key_sequence().is_empty() {
+        report(
+            Severity::Error,
+            None,
+            "Shortcut triggered on start or exit cannot have `keys` or `sequence` defined"
+                .to_string(),
+        );
+    }
+    match &shortcut.schedule {
+        Some(Schedule::EveryMs(every_ms)) if *every_ms == 0 || *every_ms > MAX_EVERY_MS => report(
+            Severity::Error,
+            None,
+            format!("`every_ms` of the `schedule` must be between 1 and {MAX_EVERY_MS} (one year)"),
+        ),
+        Some(Schedule::Cron(expression)) => {
+            if let Err(e) = parse_cron(expression) {
+                report(Severity::Error, None, format!("{e:#}"));
+            }
+        }
+        _ => {}
+    }
+    if shortcut.actions.is_empty() {
+        report(
+            Severity::Warning,
+            None,
+            "Shortcut does not have any action".to_string(),
+        );
+    }
+
+    for name in shortcut.variables.keys() {
+        if let Err(message) = check_initial_variable_name(name) {
+            report(Severity::Error, None, message);
+        }
+    }
+
+    let actions_count = shortcut.actions.len();
+    for (step_index, action) in shortcut.actions.iter().enumerate() {
+        let mut report_step =
+            |severity: Severity, message: String| report(severity, Some(step_index), message);
+
+        match action {
+            Action::SetVariable { name, .. } | Action::DeleteVariable { name } => {
+                check_variable_name(name, &mut report_step);
+            }
+            Action::IncrementVariable { name, amount } => {
+                check_variable_name(name, &mut report_step);
+                if is_literal(amount) && amount.parse::<i64>().is_err() {
+                    report_step(
+                        Severity::Error,
+                        format!("`amount` must be a valid integer, got `{amount}`"),
+                    );
+                }
+            }
+            Action::Sleep { duration_ms } => {
+                if is_literal(duration_ms) && duration_ms.parse::<u64>().is_err() {
+                    report_step(
+                        Severity::Error,
+                        format!(
+                            "`duration_ms` must be a valid positive integer, got `{duration_ms}`"
+                        ),
+                    );
+                }
+            }
+            Action::GoToStep { step } => {
+                check_step("step", step, actions_count, &mut report_step);
+            }
+            Action::GoToStepRelative { step } => {
+                check_step_relative("step", step, step_index, actions_count, &mut report_step);
+            }
+            Action::IfElse {
+                operation,
+                a,
+                b,
+                step_true,
+                step_false,
+            } => {
+                check_comparison(operation, a, b, &mut report_step);
+                check_step("step_true", step_true, actions_count, &mut report_step);
+                check_step("step_false", step_false, actions_count, &mut report_step);
+            }
+            Action::IfElseRelative {
+                operation,
+                a,
+                b,
+                step_true,
+                step_false,
+            } => {
+                check_comparison(operation, a, b, &mut report_step);
+                check_step_relative(
+                    "step_true",
+                    step_true,
+                    step_index,
+                    actions_count,
+                    &mut report_step,
+                );
+                check_step_relative(
+                    "step_false",
+                    step_false,
+                    step_index,
+                    actions_count,
+                    &mut report_step,
+                );
+            }
+            Action::SwitchMode { mode } => {
+                if is_literal(mode) && !config.has_mode(mode) {
+                    report_step(Severity::Error, format!("Mode `{mode}` does not exist"));
+                }
+            }
+            Action::CallShortcut { id, .. } => {
+                if is_literal(id) && config.find_shortcut_by_id(id).is_none() {
+                    report_step(Severity::Error, format!("No shortcut found with id `{id}`"));
+                }
+            }
+            Action::Spawn { command, .. } => {
+                if command.trim().is_empty() {
+                    report_step(Severity::Error, "`command` is empty".to_string());
+                }
+            }
+            Action::AskChatGPT { .. } => match &config.openai_api_key {
+                Secret::Plain(key) if key.trim().is_empty() || key.trim() == "sk-..." => {
+                    report_step(
+                        Severity::Error,
+                        "OpenAI API key is empty or not set in configuration file".to_string(),
+                    );
+                }
+                secret => {
+                    if let Err(e) = secret.check_available() {
+                        report_step(Severity::Error, format!("OpenAI API key - {e}"));
+                    }
+                }
+            },
+            Action::Debug
+            | Action::EndProgram
+            | Action::PrintConsole { .. }
+            | Action::ShowDialog { .. }
+            | Action::ReadClipboard
+            | Action::WriteClipboard { .. } => {}
+        }
+    }
+}
+
+/// Whether a parameter can be checked before running the shortcut (does not use variables tags).
+fn is_literal(value: &str) -> bool {
+    !value.contains("{{")
+}
+
+/// Check the name of a variable declared in `variables`
+fn check_initial_variable_name(name: &str) -> Result<(), String> {
+    match name.to_lowercase().as_str() {
+        "" => Err("Variable name in `variables` is empty".to_string()),
+        "input" | MODE_VARIABLE => Err(format!(
+            "Variable `{name}` can not be declared in `variables`, it is set when running"
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn check_variable_name(name: &str, report: &mut impl FnMut(Severity, String)) {
+    if name.trim().is_empty() {
+        report(Severity::Error, "Variable `name` is empty".to_string());
+    } else if !is_literal(name) {
+        report(
+            Severity::Warning,
+            format!("Variable `name` should be the variable name directly (e.g. `my_variable`), not a tag, got `{name}`"),
+        );
+    }
+}
+
+fn check_step(
+    field: &str,
+    step: &str,
+    actions_count: usize,
+    report: &mut impl FnMut(Severity, String),
+) {
+    if !is_literal(step) {
+        return;
+    }
+    match parse_step(step) {
+        Ok(target) if target >= actions_count => report(
+            Severity::Error,
+            format!(
+                "`{field}` {target} is out of bounds [0, {}]",
+                actions_count - 1
+            ),
+        ),
+        Ok(_) => {}
+        Err(_) => report(
+            Severity::Error,
+            format!("`{field}` must be a valid integer, got `{step}`"),
+        ),
+    }
+}
+
+fn check_step_relative(
+    field: &str,
+    step: &str,
+    step_index: usize,
+    actions_count: usize,
+    report: &mut impl FnMut(Severity, String),
+) {
+    if !is_literal(step) {
+        return;
+    }
+    match parse_step_relative(step) {
+        Ok((relative, sign_is_positive)) => {
+            let out_of_bounds = if sign_is_positive {
+                match step_index.checked_add(relative) {
+                    Some(target) => target >= actions_count,
+                    None => true,
+                }
+            } else {
+                relative > step_index
+            };
+            if out_of_bounds {
+                report(
+                    Severity::Error,
+                    format!(
+                        "`{field}` {step} from step {step_index} is out of bounds [0, {}]",
+                        actions_count - 1
+                    ),
+                );
+            }
+        }
+        Err(_) => report(
+            Severity::Error,
+            format!("`{field}` must be a valid relative integer (e.g. `+2`, `-1`), got `{step}`"),
+        ),
+    }
+}
+
+fn check_comparison(operation: &str, a: &str, b: &str, report: &mut impl FnMut(Severity, String)) {
+    if !is_literal(operation) {
+        return;
+    }
+    if NumberOperator::from(operation).is_some() {
+        for (name, value) in [("a", a), ("b", b)] {
+            if is_literal(value) && value.parse::<f64>().is_err() {
+                report(
+                    Severity::Error,
+                    format!("`{name}` must be a valid number for operation `{operation}`, got `{value}`"),
+                );
+            }
+        }
+    } else if StringOperator::from(operation).is_none() {
+        report(Severity::Error, format!("Unknown operation `{operation}`"));
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use glob::Pattern;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::dispatch::ShortcutRunner;
+use crate::hotkey::is_false;
+use crate::mode::current_mode;
+use crate::Config;
+
+/// Quiet time after the last change of a file before running its shortcut, so a file copied or
+/// downloaded in chunks runs the shortcut once, with the complete file
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Files to watch to run a shortcut when they change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct FileWatch {
+    /// File or directory to watch, e.g. `~/Pictures`. `~` is the home directory, a relative path
+    /// is relative to the configuration file.
+    pub path: String,
+    /// Only run for the changed files matching this glob pattern, relative to the watched
+    /// directory, e.g. `*.png`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glob: Option<String>,
+    /// Also watch the files of the subdirectories. Default is `false`.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub recursive: bool,
+    /// Kinds of changes to run for. Default is `["create", "modify"]`.
+    #[serde(default = "default_file_events")]
+    pub events: Vec<FileEvent>,
+}
+
+/// Kind of change of a watched file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FileEvent {
+    /// The file was created, or moved to the watched path.
+    Create,
+    /// The content or the metadata of the file changed.
+    Modify,
+    /// The file was removed, or moved away from the watched path.
+    Remove,
+}
+
+fn default_file_events() -> Vec<FileEvent> {
+    vec![FileEvent::Create, FileEvent::Modify]
+}
+
+impl FileWatch {
+    /// Get the path to watch, with `~` replaced by the home directory.
+    pub fn expanded_path(&self) -> PathBuf {
+        match (self.path.strip_prefix('~'), dirs::home_dir()) {
+            (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+                home.join(rest.trim_start_matches(['/', '\\']))
+            }
+            _ => PathBuf::from(&self.path),
+        }
+    }
+
+    /// Make a relative `path` relative to `base_dir` instead, the directory of the configuration
+    /// file, so it does not depend on the current directory.
+    pub fn resolve_relative_path(&mut self, base_dir: &Path) {
+        if !self.path.starts_with('~') && Path::new(&self.path).is_relative() {
+            self.path = base_dir.join(&self.path).to_string_lossy().into_owned();
+        }
+    }
+
+    pub fn glob_pattern(&self) -> anyhow::Result<Option<Pattern>> {
+        self.glob
+            .as_deref()
+            .map(Pattern::new)
+            .transpose()
+            .with_context(|| format!("Invalid glob pattern `{}`", self.glob.as_deref().unwrap()))
+    }
+}
+
+impl fmt::Display for FileWatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "watch `{}`", self.path)?;
+        if let Some(glob) = &self.glob {
+            write!(f, " `{glob}`")?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs the shortcuts with a `watch` when their files change, with the path of the changed file
+/// as `{{input}}`. The files are not watched anymore once dropped.
+///
+/// A watching shortcut only runs while its mode is active.
+pub struct FileWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+/// A shortcut watching files.
+struct WatchedShortcut {
+    mode: String,
+    shortcut_index: usize,
+    /// File or directory watched, canonicalized to compare it with the changed paths.
+    path: PathBuf,
+    glob: Option<Pattern>,
+    recursive: bool,
+    events: Vec<FileEvent>,
+}
+
+impl WatchedShortcut {
+    fn matches(&self, event: FileEvent, changed_path: &Path) -> bool {
+        if !self.events.contains(&event) {
+            return false;
+        }
+        if changed_path == self.path {
+            return true;
+        }
+        let Ok(relative_path) = changed_path.strip_prefix(&self.path) else {
+            return false;
+        };
+        let is_nested = relative_path.components().count() > 1;
+        let matches_glob = match &self.glob {
+            Some(glob) => glob.matches_path(relative_path),
+            None => true,
+        };
+        (self.recursive || !is_nested) && matches_glob
+    }
+}
+
+impl FileWatcher {
+    /// Start watching the files of the shortcuts of every mode, and run them with `runner`.
+    ///
+    /// The paths that can not be watched, e.g. because they do not exist, are reported and
+    /// skipped.
+    pub fn start(
+        config: Arc<Config>,
+        runner: Arc<dyn ShortcutRunner>,
+    ) -> anyhow::Result<FileWatcher> {
+        let (tx, rx) = mpsc::channel::<(FileEvent, PathBuf)>();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                if let Ok(event) = event {
+                    for change in file_events(&event) {
+                        tx.send(change).ok();
+                    }
+                }
+            })?;
+
+        let watched = watched_shortcuts(&config);
+        // A single file is watched through its directory, to still see it when it is created
+        // after the start or deleted and created again
+        let mut watched_dirs: HashMap<PathBuf, bool> = HashMap::new();
+        for watched in &watched {
+            let (dir, recursive) = if watched.path.is_dir() {
+                (watched.path.as_path(), watched.recursive)
+            } else {
+                (watched.path.parent().unwrap_or(&watched.path), false)
+            };
+            *watched_dirs.entry(dir.to_path_buf()).or_default() |= recursive;
+        }
+        for (dir, recursive) in watched_dirs {
+            let mode = if recursive {
+                RecursiveMode::Recursive
+            } else {
+                RecursiveMode::NonRecursive
+            };
+            if let Err(e) = watcher.watch(&dir, mode) {
+                eprintln!("Could not watch {dir:?}: {e}");
+            }
+        }
+
+        thread::spawn(move || run_watches(&config, runner.as_ref(), &watched, rx));
+        Ok(FileWatcher { _watcher: watcher })
+    }
+}
+
+/// Get the shortcuts of every mode watching files.
+fn watched_shortcuts(config: &Config) -> Vec<WatchedShortcut> {
+    let mut watched = Vec::new();
+    for (mode, shortcuts) in config.shortcuts_by_mode() {
+        for (shortcut_index, shortcut) in shortcuts.iter().enumerate() {
+            let Some(watch) = &shortcut.watch else {
+                continue;
+            };
+            let path = watch.expanded_path();
+            let path = match canonical_watch_path(&path) {
+                Ok(path) => path,
+                Err(e) => {
+                    eprintln!("Could not watch {path:?}: {e:#}");
+                    continue;
+                }
+            };
+            watched.push(WatchedShortcut {
+                mode: mode.to_string(),
+                shortcut_index,
+                path,
+                // Errors were already reported by the validation
+                glob: watch.glob_pattern().ok().flatten(),
+                recursive: watch.recursive,
+                events: watch.events.clone(),
+            });
+        }
+    }
+    watched
+}
+
+/// Canonicalize a watched path to compare it with the changed paths. A file that does not exist
+/// yet is resolved through its directory, to still run its shortcut once it is created.
+fn canonical_watch_path(path: &Path) -> anyhow::Result<PathBuf> {
+    if let Ok(path) = path.canonicalize() {
+        return Ok(path);
+    }
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("{path:?} is not a file nor a directory"))?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let dir = dir
+        .canonicalize()
+        .with_context(|| format!("Could not find the directory {dir:?}"))?;
+    eprintln!("Watched path {path:?} does not exist yet, its shortcut runs once it is created");
+    Ok(dir.join(file_name))
+}
+
+/// Run the shortcuts matching the changes, once the changes of a path settled.
+fn run_watches(
+    config: &Arc<Config>,
+    runner: &dyn ShortcutRunner,
+    watched: &[WatchedShortcut],
+    rx: mpsc::Receiver<(FileEvent, PathBuf)>,
+) {
+    // Last change of the paths, by watched shortcut index and changed path
+    let mut pending: HashMap<(usize, PathBuf), Instant> = HashMap::new();
+    loop {
+        match rx.recv_timeout(WATCH_DEBOUNCE) {
+            Ok((event, path)) => {
+                for (i, watched) in watched.iter().enumerate() {
+                    if watched.matches(event, &path) {
+                        pending.insert((i, path.clone()), Instant::now());
+                    }
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        let mode = current_mode();
+        pending.retain(|(i, path), last_change| {
+            if last_change.elapsed() < WATCH_DEBOUNCE {
+                return true;
+            }
+            let watched = &watched[*i];
+            if watched.mode == mode {
+                let shortcut = &config.shortcuts_for_mode(&watched.mode)[watched.shortcut_index];
+                runner.run_with_input(shortcut, config, &path.to_string_lossy());
+            }
+            false
+        });
+    }
+}
+
+/// Get the paths changed by a file system event, and how each of them changed.
+fn file_events(event: &notify::Event) -> Vec<(FileEvent, PathBuf)> {
+    let kind = match event.kind {
+        EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+            FileEvent::Create
+        }
+        EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+            FileEvent::Remove
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+            // The paths are the old path then the new path
+            return [FileEvent::Remove, FileEvent::Create]
+                .into_iter()
+                .zip(event.paths.iter().cloned())
+                .collect();
+        }
+        EventKind::Modify(_) => FileEvent::Modify,
+        _ => return Vec::new(),
+    };
+    event
+        .paths
+        .iter()
+        .map(|path| (kind, path.clone()))
+        .collect()
+}
@@ -0,0 +1,50 @@
+//! Fixtures shared by the integration tests, every test file does not use all of them.
+#![allow(dead_code)]
+
+use std::sync::{Arc, Mutex};
+
+use shortcut_hero::dispatch::{Dispatcher, ShortcutRunner};
+use shortcut_hero::hotkey::Shortcut;
+use shortcut_hero::input::SimulatedBackend;
+use shortcut_hero::Config;
+
+/// Record the ids of the shortcuts run with their input, and the aborts.
+#[derive(Default)]
+pub struct Recorder(Mutex<Vec<(String, String)>>);
+
+impl Recorder {
+    /// Take the ids and inputs of the shortcuts run since the last call.
+    pub fn take(&self) -> Vec<(String, String)> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+
+    /// Take the ids of the shortcuts run since the last call.
+    pub fn take_ids(&self) -> Vec<String> {
+        self.take().into_iter().map(|(id, _)| id).collect()
+    }
+}
+
+impl ShortcutRunner for Recorder {
+    fn run_with_input(&self, shortcut: &Shortcut, _config: &Arc<Config>, input: &str) {
+        let id = shortcut.id.clone().unwrap_or_default();
+        self.0.lock().unwrap().push((id, input.to_string()));
+    }
+
+    fn cancel_all(&self) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(("abort".to_string(), String::new()));
+    }
+}
+
+/// Bind the shortcuts of `config` on a simulated keyboard.
+pub fn setup(config: &str) -> (Arc<SimulatedBackend>, Arc<Recorder>) {
+    let config: Config = serde_json::from_str(config).unwrap();
+    let backend = SimulatedBackend::new();
+    let runs = Arc::new(Recorder::default());
+
+    let dispatcher = Dispatcher::new(Arc::new(config), backend.clone(), runs.clone());
+    dispatcher.bind();
+    (backend, runs)
+}
@@ -0,0 +1,354 @@
+use std::fs;
+use std::path::PathBuf;
+
+use shortcut_hero::config::ConfigFormat;
+use shortcut_hero::hotkey::{Concurrency, Trigger};
+use shortcut_hero::schedule::Schedule;
+use shortcut_hero::validation::{validate_config, Severity};
+use shortcut_hero::Config;
+
+/// Write the configuration files in a new directory, returns the directory.
+fn write_files(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("shortcut-hero-{name}-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for (file_name, content) in files {
+        fs::write(dir.join(file_name), content).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn duplicate_ids_are_reported_unless_overridden_by_a_later_file() {
+    let dir = write_files(
+        "duplicate-ids",
+        &[
+            (
+                "team.json",
+                r#"{"version": 1, "keyboard_shortcuts": [{"id": "a", "description": "Team"}]}"#,
+            ),
+            (
+                "shortcut-hero.json",
+                r#"{
+                    "version": 1,
+                    "include": ["team.json"],
+                    "keyboard_shortcuts": [
+                        {"id": "a", "description": "Mine"},
+                        {"id": "b", "description": "First"},
+                        {"id": "b", "description": "Second"}
+                    ],
+                    "modes": {"writing": {"keyboard_shortcuts": [{"id": "a"}]}}
+                }"#,
+            ),
+        ],
+    );
+    let config = Config::read_config(&dir.join("shortcut-hero.json")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let descriptions: Vec<&str> = config
+        .keyboard_shortcuts
+        .iter()
+        .map(|shortcut| shortcut.description.as_str())
+        .collect();
+    assert_eq!(descriptions, ["Mine", "First", "Second"]);
+
+    let errors: Vec<String> = validate_config(&config)
+        .into_iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .map(|diagnostic| diagnostic.to_string())
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            "error [shortcut 2 `b`]: Id `b` is already used by shortcut 1 of mode `normal`, ids \
+             must be unique across every mode",
+            "error [mode writing] [shortcut 0 `a`]: Id `a` is already used by shortcut 0 of mode \
+             `normal`, ids must be unique across every mode",
+        ]
+    );
+}
+
+#[test]
+fn relative_paths_are_relative_to_the_config_file() {
+    let dir = write_files(
+        "relative-paths",
+        &[
+            ("key.txt", "sk-test\n"),
+            (
+                "shortcut-hero.json",
+                r#"{
+                    "version": 1,
+                    "openai_api_key": {"from_file": "key.txt"},
+                    "keyboard_shortcuts": [{"watch": {"path": "screenshots"}}]
+                }"#,
+            ),
+        ],
+    );
+    let config = Config::read_config(&dir.join("shortcut-hero.json")).unwrap();
+    let dir = dir.canonicalize().unwrap();
+
+    assert_eq!(config.openai_api_key.resolve().unwrap(), "sk-test");
+    let watch = config.keyboard_shortcuts[0].watch.as_ref().unwrap();
+    assert_eq!(watch.expanded_path(), dir.join("screenshots"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn modes_left_through_a_called_shortcut_are_not_reported() {
+    let config: Config = serde_json::from_str(
+        r#"{
+            "keyboard_shortcuts": [
+                {"id": "back", "actions": [{"action": "switch_mode", "mode": "normal"}]},
+                {"id": "call_back", "actions": [{"action": "call_shortcut", "id": "back"}]},
+                {"id": "loop", "actions": [
+                    {"action": "call_shortcut", "id": "loop"},
+                    {"action": "call_shortcut", "id": "loop"}
+                ]}
+            ],
+            "modes": {
+                "writing": {"keyboard_shortcuts": [
+                    {"id": "writing", "actions": [{"action": "call_shortcut", "id": "call_back"}]}
+                ]},
+                "stuck": {"keyboard_shortcuts": [
+                    {"id": "stuck", "actions": [{"action": "call_shortcut", "id": "loop"}]}
+                ]}
+            }
+        }"#,
+    )
+    .unwrap();
+
+    let warnings: Vec<String> = validate_config(&config)
+        .into_iter()
+        .filter(|diagnostic| diagnostic.message.contains("no way back"))
+        .map(|diagnostic| diagnostic.to_string())
+        .collect();
+    assert_eq!(
+        warnings,
+        [
+            "warning [mode stuck]: No shortcut of this mode switches to another mode, there is no \
+             way back once switched to it"
+        ]
+    );
+}
+
+#[test]
+fn schema_shows_the_defaults() {
+    let schema = serde_json::to_value(Config::json_schema()).unwrap();
+    let properties = &schema["properties"];
+    assert_eq!(properties["sequence_timeout_ms"]["default"], 1000);
+    assert_eq!(properties["max_concurrent_runs"]["default"], 4);
+}
+
+#[test]
+fn yaml_enums_are_maps_like_in_json() {
+    let config = ConfigFormat::Yaml
+        .parse(
+            r#"
+keyboard_shortcuts:
+  - id: every
+    schedule: { every_ms: 60000 }
+"#,
+        )
+        .unwrap();
+    let schedule = config.keyboard_shortcuts[0].schedule.clone();
+    assert_eq!(schedule, Some(Schedule::EveryMs(60000)));
+
+    let data = ConfigFormat::Yaml.serialize(&config).unwrap();
+    assert!(data.contains("every_ms: 60000"));
+    assert!(!data.contains('!'));
+}
+
+#[test]
+fn every_trigger_concurrency_and_schedule_converts_between_formats() {
+    let json = r#"{
+        "version": 1,
+        "keyboard_shortcuts": [
+            {"id": "press", "keys": "B", "trigger": "press", "concurrency": "parallel"},
+            {"id": "release", "keys": "B", "trigger": "release", "concurrency": "queue"},
+            {"id": "hold", "keys": "B", "trigger": {"hold": {"min_duration_ms": 500}}, "concurrency": "drop_if_running"},
+            {"id": "double_tap", "keys": "B", "trigger": {"double_tap": {"within_ms": 300}}, "concurrency": "restart"},
+            {"id": "on_start", "trigger": "on_start"},
+            {"id": "on_exit", "trigger": "on_exit"},
+            {"id": "every", "schedule": {"every_ms": 60000}},
+            {"id": "cron", "schedule": {"cron": "0 9 * * Mon-Fri"}}
+        ]
+    }"#;
+    let config = ConfigFormat::Json.parse(json).unwrap();
+
+    let toml = ConfigFormat::Toml.serialize(&config).unwrap();
+    let from_toml = ConfigFormat::Toml.parse(&toml).unwrap();
+    let yaml = ConfigFormat::Yaml.serialize(&from_toml).unwrap();
+    let from_yaml = ConfigFormat::Yaml.parse(&yaml).unwrap();
+    assert_eq!(
+        ConfigFormat::Json.serialize(&from_yaml).unwrap(),
+        ConfigFormat::Json.serialize(&config).unwrap()
+    );
+
+    let shortcuts = &from_yaml.keyboard_shortcuts;
+    let triggers: Vec<&Trigger> = shortcuts.iter().map(|shortcut| &shortcut.trigger).collect();
+    assert_eq!(
+        triggers[..6],
+        [
+            &Trigger::Press,
+            &Trigger::Release,
+            &Trigger::Hold {
+                min_duration_ms: 500
+            },
+            &Trigger::DoubleTap { within_ms: 300 },
+            &Trigger::OnStart,
+            &Trigger::OnExit,
+        ]
+    );
+    let concurrencies: Vec<Concurrency> = shortcuts[..4]
+        .iter()
+        .map(|shortcut| shortcut.concurrency)
+        .collect();
+    assert_eq!(
+        concurrencies,
+        [
+            Concurrency::Parallel,
+            Concurrency::Queue,
+            Concurrency::DropIfRunning,
+            Concurrency::Restart,
+        ]
+    );
+    assert_eq!(shortcuts[6].schedule, Some(Schedule::EveryMs(60000)));
+    assert_eq!(
+        shortcuts[7].schedule,
+        Some(Schedule::Cron("0 9 * * Mon-Fri".to_string()))
+    );
+}
+
+#[test]
+fn toml_and_yaml_files_convert_to_each_other() {
+    let dir = write_files(
+        "formats",
+        &[
+            (
+                "shortcut-hero.toml",
+                r#"
+version = 1
+abort_keys = "Ctrl+Alt+Escape"
+
+[variables]
+language = "French"
+
+[[keyboard_shortcuts]]
+id = "explain"
+keys = "Ctrl+E"
+
+[[keyboard_shortcuts.actions]]
+action = "read_clipboard"
+
+[[keyboard_shortcuts.actions]]
+action = "ask_chatgpt"
+pre_prompt = """
+Explain the following text in {{language}}.
+Keep it short.
+"""
+
+[[modes.writing.keyboard_shortcuts]]
+id = "normal"
+keys = "Escape"
+actions = [{ action = "switch_mode", mode = "normal" }]
+"#,
+            ),
+            (
+                "shortcut-hero.yaml",
+                r#"
+version: 1
+keyboard_shortcuts:
+  - id: every
+    schedule: { every_ms: 60000 }
+    actions:
+      - action: debug
+"#,
+            ),
+        ],
+    );
+
+    let toml = Config::read_config_without_includes(&dir.join("shortcut-hero.toml")).unwrap();
+    assert_eq!(toml.variables["language"], "French");
+    assert_eq!(toml.modes["writing"].keyboard_shortcuts.len(), 1);
+    let prompt = serde_json::to_value(&toml.keyboard_shortcuts[0].actions[1]).unwrap();
+    assert_eq!(
+        prompt["pre_prompt"],
+        "Explain the following text in {{language}}.\nKeep it short.\n"
+    );
+
+    let yaml = Config::read_config_without_includes(&dir.join("shortcut-hero.yaml")).unwrap();
+    assert_eq!(
+        yaml.keyboard_shortcuts[0].schedule,
+        Some(Schedule::EveryMs(60000))
+    );
+
+    // Like `shortcut-hero convert`, every format reads back the same configuration
+    for (config, name) in [(&toml, "toml"), (&yaml, "yaml")] {
+        let expected = ConfigFormat::Json.serialize(config).unwrap();
+        for extension in ["json", "toml", "yaml", "yml"] {
+            let converted_path = dir.join(format!("{name}-converted.{extension}"));
+            Config::save_config(config, &converted_path).unwrap();
+            let converted = Config::read_config_without_includes(&converted_path).unwrap();
+            assert_eq!(
+                ConfigFormat::Json.serialize(&converted).unwrap(),
+                expected,
+                "{name} converted to {extension}"
+            );
+        }
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// A configuration written before the versions, with the old action and parameter names.
+const V0_CONFIG: &str = r#"{
+    "openai_api_key": "sk-test",
+    "keyboard_shortcuts": [{
+        "keys": ["LControlKey", "BKey"],
+        "actions": [
+            {"action": "openai_ask_chatgpt", "pre_prompt": "Explain"},
+            {"action": "go_to_step_relative", "step_relative": "-1"}
+        ]
+    }]
+}"#;
+
+#[test]
+fn older_config_is_upgraded_with_a_backup() {
+    let dir = write_files("upgrade", &[("shortcut-hero.json", V0_CONFIG)]);
+    let path = dir.join("shortcut-hero.json");
+    Config::load_and_upgrade_config(&path).unwrap();
+
+    let backup = fs::read_to_string(dir.join("shortcut-hero.json.v0.bak")).unwrap();
+    assert_eq!(backup, V0_CONFIG);
+    let upgraded: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(upgraded["version"], 1);
+    let actions = &upgraded["keyboard_shortcuts"][0]["actions"];
+    assert_eq!(actions[0]["action"], "ask_chatgpt");
+    assert_eq!(actions[1]["step"], "-1");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn older_config_is_upgraded_in_memory_only_when_read() {
+    let dir = write_files("read-only-upgrade", &[("shortcut-hero.json", V0_CONFIG)]);
+    let path = dir.join("shortcut-hero.json");
+    let config = Config::load_config(&path).unwrap();
+
+    let action = serde_json::to_value(&config.keyboard_shortcuts[0].actions[0]).unwrap();
+    assert_eq!(action["action"], "ask_chatgpt");
+    assert_eq!(fs::read_to_string(&path).unwrap(), V0_CONFIG);
+    assert!(!dir.join("shortcut-hero.json.v0.bak").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn newer_config_is_refused_without_rewriting_it() {
+    let newer = r#"{"version": 99, "keyboard_shortcuts": []}"#;
+    let dir = write_files("newer", &[("shortcut-hero.json", newer)]);
+    let path = dir.join("shortcut-hero.json");
+
+    let error = Config::load_and_upgrade_config(&path).unwrap_err();
+    assert!(format!("{error:#}").contains("newer than the supported version"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), newer);
+    assert!(!dir.join("shortcut-hero.json.v99.bak").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,243 @@
+use std::sync::RwLock;
+use std::thread::sleep;
+use std::time::Duration;
+
+use shortcut_hero::hotkey::{KeybdKey, MouseButton, PhysicalKey};
+use shortcut_hero::input::{InputEvent, SimulatedBackend};
+use shortcut_hero::keys::{format_key_sequence, parse_key_sequence};
+use shortcut_hero::mode::{set_current_mode, DEFAULT_MODE};
+
+use common::setup;
+
+mod common;
+
+const CTRL: PhysicalKey = PhysicalKey::Keyboard(KeybdKey::LControlKey);
+const RCTRL: PhysicalKey = PhysicalKey::Keyboard(KeybdKey::RControlKey);
+const SHIFT: PhysicalKey = PhysicalKey::Keyboard(KeybdKey::LShiftKey);
+const ALT: PhysicalKey = PhysicalKey::Keyboard(KeybdKey::LAltKey);
+const ESCAPE: PhysicalKey = PhysicalKey::Keyboard(KeybdKey::EscapeKey);
+const B: PhysicalKey = PhysicalKey::Keyboard(KeybdKey::BKey);
+const C: PhysicalKey = PhysicalKey::Keyboard(KeybdKey::CKey);
+const K: PhysicalKey = PhysicalKey::Keyboard(KeybdKey::KKey);
+const R: PhysicalKey = PhysicalKey::Keyboard(KeybdKey::RKey);
+
+/// The current mode is global, held for writing by the tests switching it so they do not run
+/// alongside the tests of the default mode.
+static MODE: RwLock<()> = RwLock::new(());
+
+fn tap(backend: &SimulatedBackend, keys: &[PhysicalKey]) {
+    keys.iter().for_each(|key| backend.press(*key));
+    keys.iter().rev().for_each(|key| backend.release(*key));
+    // Let the dispatcher notice the keys were released
+    sleep(Duration::from_millis(50));
+}
+
+fn wait(ms: u64) -> InputEvent {
+    InputEvent::Wait(Duration::from_millis(ms))
+}
+
+#[test]
+fn combo_runs_when_every_key_is_pressed() {
+    let _mode = MODE.read().unwrap();
+    let (backend, runs) =
+        setup(r#"{"keyboard_shortcuts": [{"id": "ctrl_b", "keys": "Ctrl+B", "actions": []}]}"#);
+    assert_eq!(backend.bound_keys().len(), 1);
+
+    tap(&backend, &[B]);
+    assert!(runs.take_ids().is_empty());
+
+    tap(&backend, &[CTRL, B]);
+    tap(&backend, &[RCTRL, B]);
+    assert_eq!(runs.take_ids(), ["ctrl_b", "ctrl_b"]);
+}
+
+#[test]
+fn exact_match_ignores_extra_modifiers() {
+    let _mode = MODE.read().unwrap();
+    let (backend, runs) = setup(
+        r#"{"keyboard_shortcuts": [
+            {"id": "exact", "keys": "Ctrl+B", "exact_match": true, "actions": []},
+            {"id": "loose", "keys": "Ctrl+B", "actions": []}
+        ]}"#,
+    );
+
+    tap(&backend, &[CTRL, SHIFT, B]);
+    assert_eq!(runs.take_ids(), ["loose"]);
+
+    tap(&backend, &[CTRL, B]);
+    assert_eq!(runs.take_ids(), ["exact", "loose"]);
+}
+
+#[test]
+fn sequence_waits_for_the_next_combo() {
+    let _mode = MODE.read().unwrap();
+    let (backend, runs) = setup(
+        r#"{"sequence_timeout_ms": 100, "keyboard_shortcuts": [
+            {"id": "ctrl_k", "keys": "Ctrl+K", "actions": []},
+            {"id": "ctrl_k_c", "sequence": "Ctrl+K C", "actions": []}
+        ]}"#,
+    );
+
+    tap(&backend, &[CTRL, K]);
+    assert!(runs.take_ids().is_empty());
+    tap(&backend, &[C]);
+    assert_eq!(runs.take_ids(), ["ctrl_k_c"]);
+
+    tap(&backend, &[CTRL, K]);
+    sleep(Duration::from_millis(300));
+    assert_eq!(runs.take_ids(), ["ctrl_k"]);
+}
+
+#[test]
+fn release_and_hold_triggers() {
+    let _mode = MODE.read().unwrap();
+    let (backend, runs) = setup(
+        r#"{"keyboard_shortcuts": [
+            {"id": "tap", "keys": "B", "trigger": "release", "actions": []},
+            {"id": "hold", "keys": "B", "trigger": {"hold": {"min_duration_ms": 100}}, "actions": []}
+        ]}"#,
+    );
+
+    backend.play(&[
+        InputEvent::Press(B),
+        wait(20),
+        InputEvent::Release(B),
+        wait(100),
+    ]);
+    assert_eq!(runs.take_ids(), ["tap"]);
+
+    // Key repeats while the key is held
+    backend.play(&[
+        InputEvent::Press(B),
+        wait(100),
+        InputEvent::Press(B),
+        wait(200),
+        InputEvent::Release(B),
+        wait(100),
+    ]);
+    assert_eq!(runs.take_ids(), ["hold"]);
+}
+
+#[test]
+fn key_repeat_needs_a_release() {
+    let _mode = MODE.read().unwrap();
+    let (backend, runs) = setup(
+        r#"{"keyboard_shortcuts": [
+            {"id": "once", "keys": "Ctrl+B", "actions": []},
+            {"id": "repeated", "keys": "Ctrl+R", "repeat": true, "actions": []}
+        ]}"#,
+    );
+
+    backend.play(&[
+        InputEvent::Press(CTRL),
+        InputEvent::Press(B),
+        InputEvent::Press(B),
+        InputEvent::Press(B),
+        InputEvent::Release(B),
+        wait(50),
+        InputEvent::Press(B),
+        InputEvent::Release(B),
+        wait(50),
+    ]);
+    assert_eq!(runs.take_ids(), ["once", "once"]);
+
+    backend.play(&[
+        InputEvent::Press(R),
+        InputEvent::Press(R),
+        InputEvent::Press(R),
+    ]);
+    assert_eq!(runs.take_ids(), ["repeated", "repeated", "repeated"]);
+}
+
+#[test]
+fn cooldown_ignores_triggers() {
+    let _mode = MODE.read().unwrap();
+    let (backend, runs) = setup(
+        r#"{"keyboard_shortcuts": [{"id": "cooldown", "keys": "B", "cooldown_ms": 200, "actions": []}]}"#,
+    );
+
+    tap(&backend, &[B]);
+    tap(&backend, &[B]);
+    assert_eq!(runs.take_ids(), ["cooldown"]);
+
+    sleep(Duration::from_millis(200));
+    tap(&backend, &[B]);
+    assert_eq!(runs.take_ids(), ["cooldown"]);
+}
+
+#[test]
+fn double_tap_trigger() {
+    let _mode = MODE.read().unwrap();
+    let (backend, runs) = setup(
+        r#"{"keyboard_shortcuts": [
+            {"id": "double", "keys": "B", "trigger": {"double_tap": {"within_ms": 200}}, "actions": []}
+        ]}"#,
+    );
+
+    tap(&backend, &[B]);
+    assert!(runs.take_ids().is_empty());
+    tap(&backend, &[B]);
+    assert_eq!(runs.take_ids(), ["double"]);
+
+    tap(&backend, &[B]);
+    sleep(Duration::from_millis(300));
+    tap(&backend, &[B]);
+    assert!(runs.take_ids().is_empty());
+}
+
+#[test]
+fn mouse_button_with_modifier() {
+    let _mode = MODE.read().unwrap();
+    let (backend, runs) =
+        setup(r#"{"keyboard_shortcuts": [{"id": "mouse", "keys": "Ctrl+Mouse4", "actions": []}]}"#);
+
+    tap(&backend, &[CTRL, PhysicalKey::Mouse(MouseButton::X1Button)]);
+    assert_eq!(runs.take_ids(), ["mouse"]);
+}
+
+#[test]
+fn abort_keys_cancel_in_any_mode() {
+    let _mode = MODE.write().unwrap();
+    let (backend, runs) = setup(
+        r#"{"abort_keys": "Ctrl+Alt+Escape", "keyboard_shortcuts": [
+            {"id": "ctrl_k_c", "sequence": "Ctrl+K C", "actions": []}
+        ], "modes": {"writing": {"keyboard_shortcuts": [
+            {"id": "writing_ctrl_k_c", "sequence": "Ctrl+K C", "actions": []}
+        ]}}}"#,
+    );
+
+    tap(&backend, &[CTRL, ALT, ESCAPE]);
+    assert_eq!(runs.take_ids(), ["abort"]);
+
+    // Also cancels the pending sequence
+    tap(&backend, &[CTRL, K]);
+    tap(&backend, &[CTRL, ALT, ESCAPE]);
+    tap(&backend, &[C]);
+    assert_eq!(runs.take_ids(), ["abort"]);
+
+    set_current_mode("writing");
+    tap(&backend, &[CTRL, K]);
+    tap(&backend, &[CTRL, ALT, ESCAPE]);
+    tap(&backend, &[C]);
+    let taken = runs.take_ids();
+    set_current_mode(DEFAULT_MODE);
+    assert_eq!(taken, ["abort"]);
+}
+
+#[test]
+fn key_strings_round_trip() {
+    for keys in [
+        "Ctrl+Shift+B",
+        "Super+F12",
+        "Ctrl+K LCtrl+C",
+        "Alt+Mouse5",
+        "RAlt+Key42",
+    ] {
+        let sequence = parse_key_sequence(keys).unwrap();
+        let sequence: Vec<_> = sequence.iter().map(Vec::as_slice).collect();
+        assert_eq!(format_key_sequence(&sequence), keys);
+    }
+    assert!(parse_key_sequence("Ctrl+Shfit+B").is_err());
+    let error = parse_key_sequence("Ctrl+WheelUp").unwrap_err();
+    assert!(format!("{error:#}").contains("mouse wheel"));
+}
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, sleep};
+use std::time::{Duration, Instant};
+
+use shortcut_hero::dispatch::ShortcutRunner;
+use shortcut_hero::executor::{CancelToken, Cancelled, Executor};
+use shortcut_hero::hotkey::{Shortcut, TriggerContext, TriggerResult};
+use shortcut_hero::Config;
+
+/// Start an executor whose runs take 100ms, returns the start, end and cancellation of each run.
+fn setup(max_concurrent_runs: usize) -> (Arc<Executor>, Arc<Mutex<Vec<String>>>) {
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let recorded_events = events.clone();
+    let executor = Executor::new(
+        max_concurrent_runs,
+        Arc::new(move |shortcut, _, _, _, cancel| {
+            let id = shortcut.id.clone().unwrap_or_default();
+            let record = |event: &str| {
+                recorded_events
+                    .lock()
+                    .unwrap()
+                    .push(format!("{event} {id}"))
+            };
+            record("start");
+            for _ in 0..10 {
+                if cancel.is_cancelled() {
+                    record("cancel");
+                    return Err(Cancelled.into());
+                }
+                sleep(Duration::from_millis(10));
+            }
+            record("end");
+            Ok(TriggerResult {
+                output: id,
+                variables: HashMap::new(),
+                actions_result: Vec::new(),
+            })
+        }),
+    );
+    (executor, events)
+}
+
+fn shortcut(json: &str) -> Shortcut {
+    serde_json::from_str(json).unwrap()
+}
+
+fn submit_twice(executor: &Executor, shortcut: &Shortcut) {
+    let config = Arc::new(Config::default());
+    executor.submit(shortcut, &config, "");
+    sleep(Duration::from_millis(30));
+    executor.submit(shortcut, &config, "");
+    executor.wait_idle();
+}
+
+fn taken(events: &Mutex<Vec<String>>) -> Vec<String> {
+    std::mem::take(&mut *events.lock().unwrap())
+}
+
+#[test]
+fn queue_runs_one_after_the_other() {
+    let (executor, events) = setup(4);
+    submit_twice(&executor, &shortcut(r#"{"id": "a", "actions": []}"#));
+    assert_eq!(taken(&events), ["start a", "end a", "start a", "end a"]);
+}
+
+#[test]
+fn parallel_runs_at_the_same_time() {
+    let (executor, events) = setup(4);
+    submit_twice(
+        &executor,
+        &shortcut(r#"{"id": "a", "concurrency": "parallel", "actions": []}"#),
+    );
+    assert_eq!(taken(&events), ["start a", "start a", "end a", "end a"]);
+}
+
+#[test]
+fn drop_if_running_ignores_the_trigger() {
+    let (executor, events) = setup(4);
+    submit_twice(
+        &executor,
+        &shortcut(r#"{"id": "a", "concurrency": "drop_if_running", "actions": []}"#),
+    );
+    assert_eq!(taken(&events), ["start a", "end a"]);
+}
+
+#[test]
+fn restart_cancels_the_current_run() {
+    let (executor, events) = setup(1);
+    submit_twice(
+        &executor,
+        &shortcut(r#"{"id": "a", "concurrency": "restart", "actions": []}"#),
+    );
+    assert_eq!(taken(&events), ["start a", "cancel a", "start a", "end a"]);
+}
+
+#[test]
+fn max_concurrent_runs_makes_runs_wait() {
+    let (executor, events) = setup(1);
+    let config = Arc::new(Config::default());
+    let a = shortcut(r#"{"id": "a", "concurrency": "parallel", "actions": []}"#);
+    let b = shortcut(r#"{"id": "b", "concurrency": "parallel", "actions": []}"#);
+    executor.submit(&a, &config, "");
+    executor.submit(&b, &config, "");
+    executor.wait_idle();
+    assert_eq!(taken(&events), ["start a", "end a", "start b", "end b"]);
+}
+
+#[test]
+fn max_concurrent_runs_can_be_raised() {
+    let (executor, events) = setup(1);
+    let config = Arc::new(Config::default());
+    let a = shortcut(r#"{"id": "a", "concurrency": "parallel", "actions": []}"#);
+    let b = shortcut(r#"{"id": "b", "concurrency": "parallel", "actions": []}"#);
+    executor.submit(&a, &config, "");
+    executor.submit(&b, &config, "");
+    sleep(Duration::from_millis(30));
+    executor.set_max_concurrent_runs(2);
+    executor.wait_idle();
+    assert_eq!(taken(&events), ["start a", "start b", "end a", "end b"]);
+}
+
+#[test]
+fn wait_idle_timeout_gives_up_on_long_runs() {
+    let (executor, events) = setup(1);
+    let config = Arc::new(Config::default());
+    let a = shortcut(r#"{"id": "a", "actions": []}"#);
+    executor.submit(&a, &config, "");
+    assert!(!executor.wait_idle_timeout(Duration::from_millis(30)));
+    assert!(executor.wait_idle_timeout(Duration::from_millis(500)));
+    assert_eq!(taken(&events), ["start a", "end a"]);
+}
+
+#[test]
+fn shortcuts_without_id_do_not_share_their_runs() {
+    let (executor, events) = setup(4);
+    // Same keys and no description, in different modes
+    let config: Config = serde_json::from_str(
+        r#"{
+            "keyboard_shortcuts": [{"keys": "F1", "concurrency": "drop_if_running", "actions": []}],
+            "modes": {"other": {"keyboard_shortcuts": [
+                {"keys": "F1", "concurrency": "drop_if_running", "actions": []}
+            ]}}
+        }"#,
+    )
+    .unwrap();
+    let config = Arc::new(config);
+    for shortcut in config.all_shortcuts() {
+        executor.submit(shortcut, &config, "");
+    }
+    executor.wait_idle();
+    let starts = taken(&events)
+        .iter()
+        .filter(|event| event.starts_with("start"))
+        .count();
+    assert_eq!(starts, 2);
+}
+
+#[test]
+fn cancel_all_cancels_running_and_queued_runs() {
+    let (executor, events) = setup(4);
+    let config = Arc::new(Config::default());
+    let a = shortcut(r#"{"id": "a", "actions": []}"#);
+    executor.submit(&a, &config, "");
+    executor.submit(&a, &config, "");
+    sleep(Duration::from_millis(30));
+    executor.cancel_all();
+    executor.wait_idle();
+    assert_eq!(taken(&events), ["start a", "cancel a"]);
+}
+
+#[test]
+fn trigger_waits_for_the_result() {
+    let (executor, events) = setup(4);
+    let config = Arc::new(Config::default());
+    let a = shortcut(r#"{"id": "a", "concurrency": "drop_if_running", "actions": []}"#);
+    executor.submit(&a, &config, "");
+    sleep(Duration::from_millis(30));
+    let dropped = executor.trigger(&a, &config, "", HashMap::new());
+    assert!(dropped.is_err());
+    executor.wait_idle();
+
+    let result = executor.trigger(&a, &config, "", HashMap::new()).unwrap();
+    assert_eq!(result.output, "a");
+    assert_eq!(taken(&events), ["start a", "end a", "start a", "end a"]);
+}
+
+#[test]
+fn trigger_reports_a_panicking_run() {
+    let executor = Executor::new(1, Arc::new(|_, _, _, _, _| panic!("Broken action")));
+    let config = Arc::new(Config::default());
+    let a = shortcut(r#"{"id": "a", "actions": []}"#);
+    for _ in 0..2 {
+        let error = executor
+            .trigger(&a, &config, "", HashMap::new())
+            .unwrap_err();
+        assert!(!error.is::<Cancelled>());
+        assert_eq!(error.to_string(), "Shortcut `a` panicked");
+    }
+}
+
+/// Trigger the actions of a shortcut and cancel the run after 50ms, returns how long it ran.
+fn cancel_after_50ms(json: &str) -> Duration {
+    let shortcut = shortcut(json);
+    let context = TriggerContext {
+        depth: 0,
+        cancel: CancelToken::default(),
+    };
+    let cancel = context.cancel.clone();
+    thread::spawn(move || {
+        sleep(Duration::from_millis(50));
+        cancel.cancel();
+    });
+
+    let started_at = Instant::now();
+    let result = shortcut.trigger_with(&Config::default(), &context, "", HashMap::new());
+    assert!(result.unwrap_err().is::<Cancelled>());
+    started_at.elapsed()
+}
+
+#[test]
+fn cancel_interrupts_sleep() {
+    let elapsed = cancel_after_50ms(
+        r#"{"actions": [
+            {"action": "sleep", "duration_ms": "5000"},
+            {"action": "print_console", "content": "Not printed"}
+        ]}"#,
+    );
+    assert!(elapsed < Duration::from_secs(1));
+}
+
+#[cfg(unix)]
+#[test]
+fn cancel_kills_spawned_command() {
+    let elapsed = cancel_after_50ms(
+        r#"{"actions": [{"action": "spawn", "command": "sleep", "args": ["5"]}]}"#,
+    );
+    assert!(elapsed < Duration::from_secs(1));
+}
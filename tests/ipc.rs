@@ -0,0 +1,115 @@
+#![cfg(unix)]
+
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::sync::{Arc, Mutex};
+
+use shortcut_hero::executor::Executor;
+use shortcut_hero::hotkey::TriggerContext;
+use shortcut_hero::ipc::{self, Request, Response};
+use shortcut_hero::Config;
+
+/// Start an executor running the actions of the shortcuts.
+fn executor() -> Arc<Executor> {
+    Executor::new(
+        1,
+        Arc::new(|shortcut, config, input, args, cancel| {
+            let context = TriggerContext {
+                depth: 0,
+                cancel: cancel.clone(),
+            };
+            shortcut.trigger_with(config, &context, input, args)
+        }),
+    )
+}
+
+#[test]
+fn trigger_and_list_through_the_socket() {
+    let config = serde_json::json!({
+        "variables": {"city": "Paris"},
+        "keyboard_shortcuts": [
+            {
+                "id": "greet",
+                "keys": "Ctrl+G",
+                "description": "Greet",
+                "actions": [
+                    {"action": "set_variable", "name": "greeting", "value": "Hi {{input}} from {{city}}"}
+                ]
+            }
+        ]
+    });
+    let config: Config = serde_json::from_value(config).unwrap();
+    let dir = std::env::temp_dir().join(format!("shortcut-hero-ipc-{}", std::process::id()));
+    let path = dir.join("shortcut-hero.sock");
+    ipc::serve(&path, Arc::new(Mutex::new(Arc::new(config))), executor()).unwrap();
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let request = Request::Trigger {
+        id: "greet".to_string(),
+        input: "rigwild".to_string(),
+        variables: HashMap::from([("city".to_string(), "Bordeaux".to_string())]),
+    };
+    let Response::Triggered { output, variables } = ipc::send(&path, &request).unwrap() else {
+        panic!("The shortcut was not triggered");
+    };
+    assert_eq!(output, "rigwild");
+    assert_eq!(variables["greeting"], "Hi rigwild from Bordeaux");
+
+    let request = Request::Trigger {
+        id: "missing".to_string(),
+        input: String::new(),
+        variables: HashMap::new(),
+    };
+    assert!(matches!(
+        ipc::send(&path, &request).unwrap(),
+        Response::Error { .. }
+    ));
+
+    let Response::Shortcuts { shortcuts } = ipc::send(&path, &Request::List).unwrap() else {
+        panic!("The shortcuts were not listed");
+    };
+    assert_eq!(shortcuts.len(), 1);
+    assert_eq!(shortcuts[0].id.as_deref(), Some("greet"));
+    assert_eq!(shortcuts[0].mode, "normal");
+
+    ipc::remove_socket();
+    assert!(!path.exists());
+    fs::remove_dir(&dir).unwrap();
+}
+
+#[test]
+fn socket_in_a_shared_directory_is_refused() {
+    let dir = std::env::temp_dir().join(format!("shortcut-hero-shared-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o777)).unwrap();
+    let path = dir.join("shortcut-hero.sock");
+
+    let config = Arc::new(Mutex::new(Arc::new(Config::default())));
+    assert!(ipc::serve(&path, config, executor()).is_err());
+    assert!(ipc::send(&path, &Request::List).is_err());
+    fs::remove_dir(&dir).unwrap();
+}
+
+#[test]
+fn trigger_variables_are_case_insensitive() {
+    let config = serde_json::json!({
+        "variables": {"lang": "French"},
+        "keyboard_shortcuts": [
+            {"id": "translate", "actions": [{"action": "set_variable", "name": "target", "value": "{{lang}}"}]}
+        ]
+    });
+    let config: Config = serde_json::from_value(config).unwrap();
+    let shortcut = config.find_shortcut("translate").unwrap();
+
+    // The order of the variables is random, a wrong override would not fail every time
+    for _ in 0..20 {
+        let args = HashMap::from([("Lang".to_string(), "German".to_string())]);
+        let result = shortcut
+            .trigger_with(&config, &TriggerContext::default(), "", args)
+            .unwrap();
+        assert_eq!(result.variables["target"], "German");
+        assert!(!result.variables.contains_key("Lang"));
+    }
+}
@@ -0,0 +1,54 @@
+use serde_json::json;
+use shortcut_hero::migration::{migrate_config, needs_migration, CONFIG_VERSION};
+
+#[test]
+fn v0_renames_the_actions_and_parameters() {
+    let mut config = json!({
+        "keyboard_shortcuts": [{
+            "keys": ["LControlKey", "BKey"],
+            "actions": [
+                {"action": "openai_ask_chatgpt", "pre_prompt": "Explain"},
+                {"action": "go_to_step_relative", "step_relative": "-1"}
+            ]
+        }]
+    });
+    assert!(needs_migration(&config).unwrap());
+
+    let changes = migrate_config(&mut config).unwrap();
+    assert_eq!(
+        changes,
+        [
+            "[shortcut 0, step 0] Renamed action `openai_ask_chatgpt` to `ask_chatgpt`",
+            "[shortcut 0, step 1] Renamed parameter `step_relative` to `step`",
+            "Upgraded config from version 0 to 1",
+        ]
+    );
+    assert_eq!(
+        config,
+        json!({
+            "version": 1,
+            "keyboard_shortcuts": [{
+                "keys": ["LControlKey", "BKey"],
+                "actions": [
+                    {"action": "ask_chatgpt", "pre_prompt": "Explain"},
+                    {"action": "go_to_step_relative", "step": "-1"}
+                ]
+            }]
+        })
+    );
+    assert!(!needs_migration(&config).unwrap());
+}
+
+#[test]
+fn newer_version_is_refused() {
+    let original = json!({"version": CONFIG_VERSION + 1, "keyboard_shortcuts": []});
+    let mut config = original.clone();
+    assert!(needs_migration(&config).is_err());
+
+    let error = migrate_config(&mut config).unwrap_err().to_string();
+    assert!(
+        error.contains("newer than the supported version"),
+        "{error}"
+    );
+    assert_eq!(config, original);
+}
@@ -0,0 +1,123 @@
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::Duration;
+
+use chrono::{Local, TimeZone, Timelike};
+use shortcut_hero::schedule::{parse_cron, Schedule, Scheduler, MAX_EVERY_MS};
+use shortcut_hero::validation::validate_config;
+use shortcut_hero::Config;
+
+use common::Recorder;
+
+mod common;
+
+#[test]
+fn every_ms_runs_until_stopped() {
+    let config: Config = serde_json::from_str(
+        r#"{"keyboard_shortcuts": [
+            {"id": "every", "schedule": {"every_ms": 100}, "actions": []},
+            {"id": "keys", "keys": "Ctrl+B", "actions": []}
+        ]}"#,
+    )
+    .unwrap();
+    let runs = Arc::new(Recorder::default());
+
+    let scheduler = Scheduler::start(Arc::new(config), runs.clone());
+    sleep(Duration::from_millis(350));
+    scheduler.stop();
+    // Let a run triggered right when stopping be recorded
+    sleep(Duration::from_millis(50));
+    let ids = runs.take_ids();
+    assert!(ids.len() >= 2, "only {} runs", ids.len());
+    assert!(ids.iter().all(|id| id == "every"));
+
+    sleep(Duration::from_millis(300));
+    assert!(runs.take_ids().is_empty());
+}
+
+#[test]
+fn every_ms_follows_the_previous_run() {
+    let schedule = Schedule::EveryMs(100);
+    let scheduled = Local.with_ymd_and_hms(2023, 5, 6, 12, 0, 0).unwrap();
+    let ms = chrono::Duration::milliseconds;
+
+    // The run was late, the next one is still 100ms after the previous one was scheduled
+    let next_run = schedule.next_run_since(scheduled, scheduled + ms(30));
+    assert_eq!(next_run.unwrap(), Some(scheduled + ms(100)));
+
+    // The missed runs are skipped
+    let next_run = schedule.next_run_since(scheduled, scheduled + ms(250));
+    assert_eq!(next_run.unwrap(), Some(scheduled + ms(300)));
+    let next_run = schedule.next_run_since(scheduled, scheduled + ms(300));
+    assert_eq!(next_run.unwrap(), Some(scheduled + ms(400)));
+}
+
+#[test]
+fn cron_next_run() {
+    let schedule = Schedule::Cron("0 9 * * Mon-Fri".to_string());
+    // Saturday
+    let after = Local.with_ymd_and_hms(2023, 5, 6, 12, 0, 0).unwrap();
+    let next_run = schedule.next_run(after).unwrap().unwrap();
+    assert_eq!(
+        next_run,
+        Local.with_ymd_and_hms(2023, 5, 8, 9, 0, 0).unwrap()
+    );
+
+    let schedule = Schedule::Cron("*/30 * * * * *".to_string());
+    let next_run = schedule.next_run(after).unwrap().unwrap();
+    assert_eq!(next_run.second(), 30);
+}
+
+#[test]
+fn cron_expression_errors() {
+    assert!(parse_cron("0 9 * *").is_err());
+    assert!(parse_cron("0 25 * * *").is_err());
+}
+
+#[test]
+fn cron_days_of_week_are_names() {
+    for expression in [
+        "0 9 * * Mon-Fri",
+        "0 9 * * Sun,Sat",
+        "0 9 * * */2",
+        "0 0 9 * * Mon",
+    ] {
+        assert!(parse_cron(expression).is_ok(), "{expression}");
+    }
+    for expression in ["0 9 * * 1-5", "0 9 * * 0", "0 9 * * Mon,3", "0 0 9 * * 7"] {
+        let error = parse_cron(expression).unwrap_err().to_string();
+        assert!(
+            error.contains("must use day names"),
+            "{expression}: {error}"
+        );
+    }
+}
+
+#[test]
+fn every_ms_out_of_range() {
+    let now = Local::now();
+    assert!(Schedule::EveryMs(MAX_EVERY_MS)
+        .next_run(now)
+        .unwrap()
+        .is_some());
+    assert!(Schedule::EveryMs(i64::MAX as u64).next_run(now).is_err());
+    assert!(Schedule::EveryMs(u64::MAX).next_run(now).is_err());
+
+    for every_ms in [0, MAX_EVERY_MS + 1] {
+        let config = serde_json::json!({
+            "keyboard_shortcuts": [{"id": "every", "schedule": {"every_ms": every_ms}, "actions": [{"action": "debug"}]}]
+        });
+        let config: Config = serde_json::from_value(config).unwrap();
+        let messages: Vec<String> = validate_config(&config)
+            .into_iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "error [shortcut 0 `every`]: `every_ms` of the `schedule` must be between 1 and \
+                 31536000000 (one year)"
+            ]
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use shortcut_hero::validation::{validate_config, Diagnostic, Severity};
+use shortcut_hero::Config;
+
+/// Validate a configuration written in JSON.
+fn diagnostics(config: serde_json::Value) -> Vec<Diagnostic> {
+    let config: Config = serde_json::from_value(config).unwrap();
+    validate_config(&config)
+}
+
+/// Check the only diagnostic is an error at `step_index` of the first shortcut of `mode`.
+fn assert_step_error(
+    diagnostics: &[Diagnostic],
+    mode: Option<&str>,
+    shortcut_id: &str,
+    step_index: usize,
+    message: &str,
+) {
+    let [diagnostic] = diagnostics else {
+        panic!("Expected one diagnostic, got {diagnostics:?}");
+    };
+    assert_eq!(diagnostic.severity, Severity::Error);
+    assert_eq!(diagnostic.mode.as_deref(), mode);
+    assert_eq!(diagnostic.shortcut_index, Some(0));
+    assert_eq!(diagnostic.shortcut_id.as_deref(), Some(shortcut_id));
+    assert_eq!(diagnostic.step_index, Some(step_index));
+    assert_eq!(diagnostic.message, message);
+}
+
+#[test]
+fn unknown_operation() {
+    let diagnostics = diagnostics(serde_json::json!({
+        "modes": {"writing": {"keyboard_shortcuts": [{
+            "id": "compare",
+            "keys": "Ctrl+B",
+            "actions": [
+                {"action": "switch_mode", "mode": "normal"},
+                {"action": "if_else", "operation": "~=", "a": "1", "b": "2", "step_true": "0", "step_false": "0"}
+            ]
+        }]}}
+    }));
+    assert_step_error(
+        &diagnostics,
+        Some("writing"),
+        "compare",
+        1,
+        "Unknown operation `~=`",
+    );
+}
+
+#[test]
+fn go_to_step_out_of_bounds() {
+    let diagnostics = diagnostics(serde_json::json!({
+        "keyboard_shortcuts": [{
+            "id": "jump",
+            "keys": "Ctrl+B",
+            "actions": [{"action": "debug"}, {"action": "go_to_step", "step": "2"}]
+        }]
+    }));
+    assert_step_error(
+        &diagnostics,
+        None,
+        "jump",
+        1,
+        "`step` 2 is out of bounds [0, 1]",
+    );
+}
+
+#[test]
+fn if_else_relative_out_of_bounds() {
+    let diagnostics = diagnostics(serde_json::json!({
+        "keyboard_shortcuts": [{
+            "id": "branch",
+            "keys": "Ctrl+B",
+            "actions": [
+                {"action": "debug"},
+                {"action": "if_else_relative", "operation": "==", "a": "1", "b": "1", "step_true": "+1", "step_false": "-1"}
+            ]
+        }]
+    }));
+    assert_step_error(
+        &diagnostics,
+        None,
+        "branch",
+        1,
+        "`step_true` +1 from step 1 is out of bounds [0, 1]",
+    );
+}
+
+#[test]
+fn huge_relative_step_is_out_of_bounds() {
+    let step = format!("+{}", usize::MAX);
+    let diagnostics = diagnostics(serde_json::json!({
+        "keyboard_shortcuts": [{
+            "id": "huge",
+            "keys": "Ctrl+B",
+            "actions": [{"action": "debug"}, {"action": "go_to_step_relative", "step": step}]
+        }]
+    }));
+    assert_step_error(
+        &diagnostics,
+        None,
+        "huge",
+        1,
+        &format!("`step` {step} from step 1 is out of bounds [0, 1]"),
+    );
+}
+
+#[test]
+fn non_numeric_literal() {
+    let diagnostics = diagnostics(serde_json::json!({
+        "keyboard_shortcuts": [{
+            "id": "compare",
+            "keys": "Ctrl+B",
+            "actions": [
+                {"action": "if_else", "operation": ">", "a": "ten", "b": "{{input}}", "step_true": "0", "step_false": "0"}
+            ]
+        }]
+    }));
+    assert_step_error(
+        &diagnostics,
+        None,
+        "compare",
+        0,
+        "`a` must be a valid number for operation `>`, got `ten`",
+    );
+}
+
+#[test]
+fn shortcut_without_keys() {
+    let diagnostics = diagnostics(serde_json::json!({
+        "keyboard_shortcuts": [
+            {"id": "keys", "keys": "Ctrl+B", "actions": [{"action": "debug"}]},
+            {"description": "Nothing", "actions": [{"action": "debug"}]}
+        ]
+    }));
+    let [diagnostic] = diagnostics.as_slice() else {
+        panic!("Expected one diagnostic, got {diagnostics:?}");
+    };
+    assert_eq!(diagnostic.severity, Severity::Error);
+    assert_eq!(diagnostic.mode, None);
+    assert_eq!(diagnostic.shortcut_index, Some(1));
+    assert_eq!(diagnostic.shortcut_id, None);
+    assert_eq!(diagnostic.step_index, None);
+    assert_eq!(
+        diagnostic.message,
+        "Shortcut does not have keyboard keys, a schedule or a trigger defined"
+    );
+}
@@ -0,0 +1,73 @@
+use std::fs;
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::Duration;
+
+use shortcut_hero::watch::FileWatcher;
+use shortcut_hero::Config;
+
+use common::Recorder;
+
+mod common;
+
+#[test]
+fn watch_runs_with_the_changed_path() {
+    let dir = std::env::temp_dir().join(format!("shortcut-hero-watch-{}", std::process::id()));
+    fs::create_dir_all(dir.join("nested")).unwrap();
+    let dir = dir.canonicalize().unwrap();
+
+    let config = serde_json::json!({
+        "keyboard_shortcuts": [
+            {"id": "png", "watch": {"path": dir, "glob": "*.png"}, "actions": []},
+            {"id": "removed", "watch": {"path": dir, "events": ["remove"]}, "actions": []}
+        ]
+    });
+    let config: Config = serde_json::from_value(config).unwrap();
+    let runs = Arc::new(Recorder::default());
+    let watcher = FileWatcher::start(Arc::new(config), runs.clone()).unwrap();
+
+    // Written in multiple steps, runs once
+    fs::write(dir.join("screenshot.png"), "a").unwrap();
+    fs::write(dir.join("screenshot.png"), "ab").unwrap();
+    fs::write(dir.join("notes.txt"), "a").unwrap();
+    // Not recursive
+    fs::write(dir.join("nested").join("nested.png"), "a").unwrap();
+    sleep(Duration::from_millis(800));
+
+    let screenshot = dir.join("screenshot.png").to_string_lossy().to_string();
+    assert_eq!(runs.take(), [("png".to_string(), screenshot.clone())]);
+
+    fs::remove_file(dir.join("screenshot.png")).unwrap();
+    sleep(Duration::from_millis(800));
+    assert_eq!(runs.take(), [("removed".to_string(), screenshot)]);
+
+    drop(watcher);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn watch_runs_for_a_file_created_after_the_start() {
+    let dir =
+        std::env::temp_dir().join(format!("shortcut-hero-watch-later-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let dir = dir.canonicalize().unwrap();
+    let path = dir.join("later.txt");
+
+    let config = serde_json::json!({
+        "keyboard_shortcuts": [{"id": "later", "watch": {"path": path}, "actions": []}]
+    });
+    let config: Config = serde_json::from_value(config).unwrap();
+    let runs = Arc::new(Recorder::default());
+    let watcher = FileWatcher::start(Arc::new(config), runs.clone()).unwrap();
+
+    fs::write(dir.join("other.txt"), "a").unwrap();
+    fs::write(&path, "a").unwrap();
+    sleep(Duration::from_millis(800));
+    assert_eq!(
+        runs.take(),
+        [("later".to_string(), path.to_string_lossy().to_string())]
+    );
+
+    drop(watcher);
+    fs::remove_dir_all(dir).unwrap();
+}